@@ -1,4 +1,7 @@
-use std::{fs::File, io::{self, BufRead}, collections::{HashSet, HashMap}};
+mod schematic;
+
+use std::{env, fs::{self, File}, io::{self, BufRead}, collections::HashMap};
+use schematic::{Schematic, Symbol};
 
 #[derive(Debug, Clone)]
 struct Number {
@@ -14,40 +17,18 @@ impl Number {
     }
 }
 
-fn read_symbols() -> HashSet<(usize, usize)> {
+fn read_symbols() -> Vec<Symbol> {
     let f = File::open("input/input.txt").unwrap();
     let lines = io::BufReader::new(f).lines();
-    let mut line_num = 1;
-    lines.map(|line| {
+    lines.enumerate().flat_map(|(i, line)| {
         let line = line.unwrap();
-        let line_set = line.char_indices()
+        line.char_indices()
             .filter(|(_i, c)| {
                 !c.is_ascii_digit() && *c != '.'
-            }).map(|(i, _c)| {
-                (line_num, i+1)
-            }).collect::<HashSet<_>>();
-        line_num = line_num + 1;
-        
-        line_set
-    }).flatten().collect()
-}
-
-fn read_gears() -> Vec<(usize, usize)> {
-    let f = File::open("input/input.txt").unwrap();
-    let lines = io::BufReader::new(f).lines();
-    let mut line_num = 1;
-    lines.map(|line| {
-        let line = line.unwrap();
-        let line_set = line.char_indices()
-            .filter(|(_i, c)| {
-                *c == '*'
-            }).map(|(i, _c)| {
-                (line_num, i+1)
-            }).collect::<HashSet<_>>();
-        line_num = line_num + 1;
-        
-        line_set
-    }).flatten().collect()
+            }).map(|(col, c)| {
+                Symbol { line: i+1, col: col+1, c }
+            }).collect::<Vec<_>>()
+    }).collect()
 }
 
 fn read_numbers() -> HashMap<usize, Vec<Number>> {
@@ -76,62 +57,44 @@ fn read_numbers() -> HashMap<usize, Vec<Number>> {
             }
         }
         let res = (line_num, v);
-        line_num = line_num + 1;
+        line_num += 1;
 
         res
     }).collect()
 }
 
-fn find_adjacent_numbers(gear: &(usize, usize), numbers: &HashMap<usize, Vec<Number>>) -> Vec<Number> {
-    let (line, col) = gear;
-    let mut res: Vec<Number> = Vec::new();
-
-    let prev_line = numbers.get(&(line-1)).unwrap().iter().filter(|n| {
-        *col >= n.col_start-1 && *col <= n.col_end
-    }).cloned().collect::<Vec<_>>();
-    let same_line = numbers.get(&line).unwrap().iter().filter(|n| {
-        *col == n.col_start-1 || *col == n.col_end
-    }).cloned().collect::<Vec<_>>();
-    let next_line = numbers.get(&(line+1)).unwrap().iter().filter(|n| {
-        *col >= n.col_start-1 && *col <= n.col_end
-    }).cloned().collect::<Vec<_>>();
-
-    res.extend(prev_line.into_iter());
-    res.extend(same_line.into_iter());
-    res.extend(next_line.into_iter());
-
-    res
-}
-
 fn main() {
     let numbers = read_numbers();
     let symbols = read_symbols();
+    let schematic = Schematic::new(&numbers, symbols);
 
     // First part
-    let sum = numbers.iter()
-        .map(|(_, v)| v)
-        .flatten()
-        .filter(|n| {
-            let same_line = symbols.contains(&(n.line, n.col_start-1)) || symbols.contains(&(n.line, n.col_end));
-            let adjacent_lines = (n.col_start-1..n.col_end+1).any(|col| {
-                symbols.contains(&(n.line-1, col)) ||
-                symbols.contains(&(n.line+1, col))
-            });
-    
-            same_line || adjacent_lines
-        }).map(|n| n.val)
+    let sum = schematic.part_numbers()
+        .map(|n| n.val)
         .sum::<u32>();
 
     println!("Sum: {}", sum);
 
     // Second part
-    let gears = read_gears();
-    let sum = gears.iter()
-        .map(|g| find_adjacent_numbers(g, &numbers))
-        .filter(|nums| nums.len() == 2)
-        .map(|nums| nums.iter().map(|n| n.val).product::<u32>())
-        .sum::<u32>();
+    let sum = schematic.sum_of_products(Some('*'), 2);
 
     println!("Sum: {}", sum);
-    
+
+    // Analysis
+    println!("Numbers adjacent to more than one symbol: {}", schematic.shared_numbers().count());
+    schematic.symbol_histogram().iter().for_each(|(c, h)| {
+        let h = h.iter().map(|(k, count)| format!("{}: {}", k, count)).collect::<Vec<_>>().join(", ");
+        println!("Symbol '{}' by number of neighbours: {}", c, h);
+    });
+
+    // Export (e.g., cargo run -- csv graph.csv)
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() == 3 {
+        let content = match args[1].as_str() {
+            "csv" => schematic.to_csv(),
+            "json" => schematic.to_json(),
+            _ => panic!("Unknown export format"),
+        };
+        fs::write(&args[2], content).unwrap();
+    }
 }
@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::Number;
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub line: usize,    // Line (first line has value 1)
+    pub col: usize,     // Column (columns start from 1)
+    pub c: char,        // Symbol character
+}
+
+// Bipartite graph between numbers and symbols: an edge connects a number
+// to every symbol that touches it (diagonals included)
+pub struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    number_edges: Vec<Vec<usize>>,  // For each number, the ids of adjacent symbols
+    symbol_edges: Vec<Vec<usize>>,  // For each symbol, the ids of adjacent numbers
+}
+
+impl Schematic {
+    pub fn new(numbers: &HashMap<usize, Vec<Number>>, symbols: Vec<Symbol>) -> Self {
+        let mut numbers = numbers.values().flatten().cloned().collect::<Vec<_>>();
+        numbers.sort_by_key(|n| (n.line, n.col_start));
+
+        let positions = symbols.iter()
+            .enumerate()
+            .map(|(id, s)| ((s.line, s.col), id))
+            .collect::<HashMap<_, _>>();
+
+        let mut number_edges = vec![Vec::new(); numbers.len()];
+        let mut symbol_edges = vec![Vec::new(); symbols.len()];
+        for (n_id, n) in numbers.iter().enumerate() {
+            for line in n.line-1..=n.line+1 {
+                for col in n.col_start-1..=n.col_end {
+                    if let Some(&s_id) = positions.get(&(line, col)) {
+                        number_edges[n_id].push(s_id);
+                        symbol_edges[s_id].push(n_id);
+                    }
+                }
+            }
+        }
+        symbol_edges.iter_mut().for_each(|v| v.sort());
+
+        Self { numbers, symbols, number_edges, symbol_edges }
+    }
+
+    // Numbers adjacent to at least one symbol
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers.iter()
+            .zip(self.number_edges.iter())
+            .filter(|(_n, edges)| !edges.is_empty())
+            .map(|(n, _edges)| n)
+    }
+
+    // Numbers adjacent to more than one symbol
+    pub fn shared_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers.iter()
+            .zip(self.number_edges.iter())
+            .filter(|(_n, edges)| edges.len() > 1)
+            .map(|(n, _edges)| n)
+    }
+
+    // Sum, over the symbols with exactly k adjacent numbers, of the product of those numbers.
+    // If symbol is None, any symbol is considered.
+    pub fn sum_of_products(&self, symbol: Option<char>, k: usize) -> u64 {
+        self.symbols.iter()
+            .zip(self.symbol_edges.iter())
+            .filter(|(s, edges)| symbol.is_none_or(|c| s.c == c) && edges.len() == k)
+            .map(|(_s, edges)| edges.iter().map(|n| self.numbers[*n].val as u64).product::<u64>())
+            .sum()
+    }
+
+    // For each symbol type, how many symbols have 0, 1, 2, ... adjacent numbers
+    pub fn symbol_histogram(&self) -> BTreeMap<char, BTreeMap<usize, usize>> {
+        let mut res: BTreeMap<char, BTreeMap<usize, usize>> = BTreeMap::new();
+        self.symbols.iter()
+            .zip(self.symbol_edges.iter())
+            .for_each(|(s, edges)| {
+                *res.entry(s.c).or_default().entry(edges.len()).or_default() += 1;
+            });
+        res
+    }
+
+    // One row for each edge of the graph
+    pub fn to_csv(&self) -> String {
+        let mut res = String::from("number_id,number_line,number_col_start,number_col_end,value,symbol_id,symbol_line,symbol_col,symbol\n");
+        for (n_id, edges) in self.number_edges.iter().enumerate() {
+            let n = &self.numbers[n_id];
+            for s_id in edges {
+                let s = &self.symbols[*s_id];
+                let c = if s.c == '"' { String::from("\"\"") } else { s.c.to_string() };
+                res.push_str(&format!("{},{},{},{},{},{},{},{},\"{}\"\n",
+                    n_id, n.line, n.col_start, n.col_end, n.val, s_id, s.line, s.col, c));
+            }
+        }
+        res
+    }
+
+    // Numbers and symbols, each with the ids of its neighbours
+    pub fn to_json(&self) -> String {
+        let ids = |v: &Vec<usize>| v.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",");
+
+        let numbers = self.numbers.iter()
+            .zip(self.number_edges.iter())
+            .enumerate()
+            .map(|(id, (n, edges))| {
+                format!("{{\"id\":{},\"line\":{},\"col_start\":{},\"col_end\":{},\"value\":{},\"symbols\":[{}]}}",
+                    id, n.line, n.col_start, n.col_end, n.val, ids(edges))
+            }).collect::<Vec<_>>();
+
+        let symbols = self.symbols.iter()
+            .zip(self.symbol_edges.iter())
+            .enumerate()
+            .map(|(id, (s, edges))| {
+                let c = match s.c {
+                    '"' => String::from("\\\""),
+                    '\\' => String::from("\\\\"),
+                    c => c.to_string(),
+                };
+                format!("{{\"id\":{},\"line\":{},\"col\":{},\"symbol\":\"{}\",\"numbers\":[{}]}}",
+                    id, s.line, s.col, c, ids(edges))
+            }).collect::<Vec<_>>();
+
+        format!("{{\"numbers\":[{}],\"symbols\":[{}]}}\n", numbers.join(","), symbols.join(","))
+    }
+}
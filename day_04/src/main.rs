@@ -1,22 +1,67 @@
-use std::{fs::File, io::{self, BufRead}, collections::{HashSet, HashMap}, cmp::min};
+use std::{fs::File, io::{self, BufRead, Lines}, collections::{HashSet, VecDeque}};
+use num::{BigUint, One, Zero};
+
+fn parse_card(line: &str) -> (usize, HashSet<u32>, HashSet<u32>) {
+    let mut split = line.split(':');
+    let id = split.next().unwrap().split_whitespace().nth(1).unwrap().parse::<usize>().unwrap();
+    let mut split = split.next().unwrap().split('|');
+    let winning = split.next().unwrap().split_whitespace()
+        .map(|num| num.parse::<u32>().unwrap())
+        .collect::<HashSet<_>>();
+    let game = split.next().unwrap().split_whitespace()
+        .map(|num| num.parse::<u32>().unwrap())
+        .collect::<HashSet<_>>();
+    (id, winning, game)
+}
 
 fn parse_input() -> Vec<(HashSet<u32>, HashSet<u32>)> {
     let f = File::open("input/input.txt").unwrap();
     let lines = io::BufReader::new(f).lines();
     lines.map(|line| {
-        let line = line.unwrap();
-        let mut split = line.split(":");
-        let mut split = split.nth(1).unwrap().split("|");
-        let winning = split.next().unwrap().split_whitespace()
-            .map(|num| num.parse::<u32>().unwrap())
-            .collect::<HashSet<_>>();
-        let game = split.next().unwrap().split_whitespace()
-            .map(|num| num.parse::<u32>().unwrap())
-            .collect::<HashSet<_>>();
+        let (_id, winning, game) = parse_card(&line.unwrap());
         (winning, game)
     }).collect::<Vec<_>>()
 }
 
+// Reads one card at a time and yields (card id, number of copies) for each card.
+// Only the extra copies won for the next cards are kept in memory: the window is
+// never longer than the maximum number of matches on a card.
+struct CardStream<B: BufRead> {
+    lines: Lines<B>,
+    pending: VecDeque<BigUint>,
+    total: BigUint,
+}
+
+impl<B: BufRead> CardStream<B> {
+    fn new(reader: B) -> Self {
+        Self { lines: reader.lines(), pending: VecDeque::new(), total: BigUint::zero() }
+    }
+
+    // Total number of cards seen so far
+    fn total(&self) -> &BigUint {
+        &self.total
+    }
+}
+
+impl<B: BufRead> Iterator for CardStream<B> {
+    type Item = (usize, BigUint);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?.unwrap();
+        let (id, win, game) = parse_card(&line);
+        let copies = self.pending.pop_front().unwrap_or_default() + BigUint::one();
+        let card_wins = win.intersection(&game).count();
+        if self.pending.len() < card_wins {
+            self.pending.resize(card_wins, BigUint::zero());
+        }
+        self.pending.iter_mut()
+            .take(card_wins)
+            .for_each(|c| *c += &copies);
+        self.total += &copies;
+        Some((id, copies))
+    }
+}
+
 fn main() {
     // First part
     let input = parse_input();
@@ -35,19 +80,14 @@ fn main() {
     println!("Sum: {}", sum);
 
     // Second part
-    let mut count = (0..input.len()).map(|i| (i, 1)).collect::<HashMap<_,_>>();
-    for card in 0..input.len() {
-        let card_count = *count.get(&card).unwrap();
-        let (win, game) = &input[card];
-        let card_wins = win.intersection(&game).count();
-        let start_id = card + 1;
-        let end_id = min(start_id + card_wins, count.len());
-        for id in start_id..end_id {
-            *count.get_mut(&id).unwrap() += card_count;
+    let f = File::open("input/input.txt").unwrap();
+    let mut cards = CardStream::new(io::BufReader::new(f));
+    let verbose = std::env::args().any(|a| a == "--verbose");
+    for (id, copies) in cards.by_ref() {
+        if verbose {
+            println!("Card {}: {}", id, copies);
         }
     }
 
-    let sum = count.iter().map(|(_, c)| c).sum::<u32>();
-
-    println!("Sum: {}", sum);
+    println!("Sum: {}", cards.total());
 }
@@ -1,21 +1,90 @@
-use std::{fs::File, io::{self, BufRead}};
+use std::{fs::File, io::{self, BufRead}, ops::Range, cmp::min, collections::{HashMap, HashSet, VecDeque}};
 use itertools::Itertools;
 
+#[derive(Clone)]
 struct CrazyMap {
     v: Vec<(u64, u64, u64)>
 }
 
 impl CrazyMap {
-    fn new(v: Vec<(u64, u64, u64)>) -> Self {
+    fn new(mut v: Vec<(u64, u64, u64)>) -> Self {
+        v.sort_by_key(|(_dest, source, _len)| *source);
         Self { v }
     }
 
     fn get(&self, n: &u64) -> u64 {
-        self.v.iter()
-            .find(|(_dest, source, len)| *n >= *source && *n < *source + *len)
+        // Rules are sorted by source: the only candidate is the last one starting before n
+        let i = self.v.partition_point(|(_dest, source, _len)| *source <= *n);
+        self.v[..i].last()
+            .filter(|(_dest, source, len)| *n < *source + *len)
             .map(|(dest, source, _len)| *n + *dest - *source)
-            .or(Some(*n))
-            .unwrap()
+            .unwrap_or(*n)
+    }
+
+    // Splits a range at the boundaries of the mapping rules.
+    // Returns the pieces as (dest, source, len), like the rules.
+    fn split(&self, r: &Range<u64>) -> Vec<(u64, u64, u64)> {
+        let mut res = Vec::new();
+        let mut start = r.start;
+        for (dest, source, len) in self.v.iter() {
+            if start >= r.end || *source >= r.end {
+                break;
+            }
+            let end = *source + *len;
+            if end <= start {
+                continue;
+            }
+            // Part not covered by any rule is mapped to itself
+            if start < *source {
+                res.push((start, start, *source - start));
+                start = *source;
+            }
+            let stop = min(end, r.end);
+            res.push((start + *dest - *source, start, stop - start));
+            start = stop;
+        }
+        if start < r.end {
+            res.push((start, start, r.end - start));
+        }
+        res
+    }
+
+    // Maps a whole range, splitting it at the boundaries of the mapping rules
+    fn get_range(&self, r: &Range<u64>) -> Vec<Range<u64>> {
+        self.split(r).into_iter()
+            .map(|(dest, _source, len)| dest..dest + len)
+            .collect()
+    }
+
+    // Map that applies self and then next
+    fn then(&self, next: &CrazyMap) -> CrazyMap {
+        let v = self.split(&(0..u64::MAX)).into_iter()
+            .flat_map(|(dest, source, len)| {
+                next.split(&(dest..dest + len)).into_iter()
+                    .map(move |(d, s, l)| (d, source + (s - dest), l))
+            })
+            .filter(|(dest, source, _len)| dest != source)
+            .coalesce(|(d1, s1, l1), (d2, s2, l2)| {
+                if s1 + l1 == s2 && d1 + l1 == d2 {
+                    Ok((d1, s1, l1 + l2))
+                } else {
+                    Err(((d1, s1, l1), (d2, s2, l2)))
+                }
+            })
+            .collect();
+        CrazyMap::new(v)
+    }
+
+    // All the ranges of values that are mapped into r
+    fn inverse(&self, r: &Range<u64>) -> Vec<Range<u64>> {
+        let v = self.split(&(0..u64::MAX)).into_iter()
+            .filter_map(|(dest, source, len)| {
+                let start = r.start.max(dest);
+                let end = r.end.min(dest + len);
+                (start < end).then(|| source + (start - dest) .. source + (end - dest))
+            })
+            .collect();
+        merge_ranges(v)
     }
 }
 
@@ -39,44 +108,139 @@ impl CrazyList {
         }
         res
     }
+
+    // Collapses all the maps into a single one
+    fn compose(&self) -> CrazyMap {
+        self.l.iter().fold(CrazyMap::new(Vec::new()), |res, m| res.then(m))
+    }
+
+    fn get_ranges(&self, ranges: Vec<Range<u64>>) -> Vec<Range<u64>> {
+        let mut res = ranges;
+        for m in self.l.iter() {
+            res = merge_ranges(res.iter().flat_map(|r| m.get_range(r)).collect());
+        }
+        res
+    }
+}
+
+// Sorts the ranges and merges the ones that overlap or touch
+fn merge_ranges(mut v: Vec<Range<u64>>) -> Vec<Range<u64>> {
+    v.sort_by_key(|r| r.start);
+    let mut res: Vec<Range<u64>> = Vec::new();
+    for r in v.into_iter().filter(|r| !r.is_empty()) {
+        match res.last_mut() {
+            Some(last) if r.start <= last.end => last.end = last.end.max(r.end),
+            _ => res.push(r),
+        }
+    }
+    res
+}
+
+// Maps between named categories (e.g., seed-to-soil)
+struct Almanac {
+    maps: Vec<(String, String, CrazyMap)>
+}
+
+impl Almanac {
+    fn new() -> Self {
+        Self { maps: Vec::new() }
+    }
+
+    fn add(&mut self, source: String, destination: String, m: CrazyMap) {
+        if self.maps.iter().any(|(s, d, _m)| *s == source && *d == destination) {
+            panic!("Duplicate map {}-to-{}", source, destination);
+        }
+        self.maps.push((source, destination, m));
+    }
+
+    // Shortest chain of maps that converts source into destination
+    fn route(&self, source: &str, destination: &str) -> Option<CrazyList> {
+        let mut prev: HashMap<&str, Option<usize>> = HashMap::from([(source, None)]);
+        let mut queue = VecDeque::from([source]);
+        while let Some(category) = queue.pop_front() {
+            if category == destination {
+                break;
+            }
+            for (i, (s, d, _m)) in self.maps.iter().enumerate() {
+                if s == category && !prev.contains_key(d.as_str()) {
+                    prev.insert(d, Some(i));
+                    queue.push_back(d);
+                }
+            }
+        }
+
+        let mut maps = Vec::new();
+        let mut category = destination;
+        while let Some(i) = *prev.get(category)? {
+            maps.push(i);
+            category = &self.maps[i].0;
+        }
+        let mut list = CrazyList::new();
+        maps.into_iter().rev().for_each(|i| list.add(self.maps[i].2.clone()));
+        Some(list)
+    }
+
+    // Every category must be reachable from first, and last must be one of them
+    fn validate(&self, first: &str, last: &str) {
+        let categories = self.maps.iter()
+            .flat_map(|(s, d, _m)| [s.as_str(), d.as_str()])
+            .chain([first, last])
+            .collect::<HashSet<_>>();
+        let unreachable = categories.into_iter()
+            .filter(|c| self.route(first, c).is_none())
+            .sorted()
+            .collect_vec();
+        if !unreachable.is_empty() {
+            panic!("Categories not reachable from {}: {}", first, unreachable.join(", "));
+        }
+    }
 }
 
-fn parse_input() -> (Vec<u64>, CrazyList) {
+fn parse_input() -> (Vec<u64>, Almanac) {
     let f = File::open("input/input.txt").unwrap();
     let mut lines = io::BufReader::new(f).lines();
     
     // Seeds
     let seeds = lines.next().unwrap().unwrap();
-    let seeds = seeds.split(":").nth(1).unwrap();
+    let seeds = seeds.split(':').nth(1).unwrap();
     let seeds = seeds.split_whitespace().map(|s| s.parse::<u64>().unwrap()).collect::<Vec<_>>();
-    lines.next();
 
     // Maps
-    let mut list = CrazyList::new();
+    let mut almanac = Almanac::new();
+    let mut header: Option<(String, String)> = None;
     let mut v = Vec::new();
-    lines.for_each(|line| {
-        let line = line.unwrap();
-        if line.len() > 0 {
-            if !line.contains(":") {
-                let mut split = line.split_whitespace();
-                v.push((
-                    split.next().unwrap().parse::<u64>().unwrap(),
-                    split.next().unwrap().parse::<u64>().unwrap(),
-                    split.next().unwrap().parse::<u64>().unwrap()
-                ));
+    for line in lines.map(|line| line.unwrap()).chain([String::new()]) {
+        let name = line.strip_suffix(" map:");
+        // A map ends at an empty line or at the next header
+        if name.is_some() || line.is_empty() {
+            if let Some((source, destination)) = header.take() {
+                almanac.add(source, destination, CrazyMap::new(std::mem::take(&mut v)));
             }
-        } else {
-            list.add(CrazyMap::new(v.clone()));
-            v = Vec::new();
         }
-    });
+        if let Some(name) = name {
+            let (source, destination) = name.split_once("-to-").expect("Invalid map header");
+            header = Some((source.to_string(), destination.to_string()));
+        } else if !line.is_empty() {
+            if header.is_none() {
+                panic!("Rule outside of a map: {}", line);
+            }
+            let mut split = line.split_whitespace();
+            v.push((
+                split.next().unwrap().parse::<u64>().unwrap(),
+                split.next().unwrap().parse::<u64>().unwrap(),
+                split.next().unwrap().parse::<u64>().unwrap()
+            ));
+        }
+    }
+    almanac.validate("seed", "location");
 
-    (seeds, list)
+    (seeds, almanac)
 }
 
 fn main() {
     // First part
-    let (seeds, list) = parse_input();
+    let (seeds, almanac) = parse_input();
+    let list = almanac.route("seed", "location").unwrap();
     let res = seeds.iter()
         .map(|s| list.get(s))
         .min().unwrap();
@@ -84,11 +248,36 @@ fn main() {
     println!("Result: {}", res);
 
     // Second part
-    // Not proud of this brute-force solution, but didn't have time to properly optimize
-    let res = seeds.iter().tuples::<(_, _)>()
-        .flat_map(|(s, len)| *s .. *s + *len)
-        .map(|s| list.get(&s))
-        .min().unwrap();
+    let ranges = seeds.iter().tuples::<(_, _)>()
+        .map(|(s, len)| *s .. *s + *len)
+        .collect_vec();
+    let res = list.get_ranges(ranges.clone())
+        .first().unwrap()
+        .start;
 
     println!("Result: {}", res);
+
+    // Single seed-to-location map
+    let seed_to_location = list.compose();
+    let closest_seeds = seed_to_location.inverse(&(res..res + 1)).into_iter()
+        .flat_map(|inv| {
+            ranges.iter()
+                .map(move |r| inv.start.max(r.start)..inv.end.min(r.end))
+                .filter(|r| !r.is_empty())
+        })
+        .collect_vec();
+    assert!(closest_seeds.iter().all(|r| r.clone().all(|s| seed_to_location.get(&s) == res)));
+
+    println!("Rules in the composed map: {}", seed_to_location.v.len());
+    println!("Seeds for location {}: {:?}", res, closest_seeds);
+
+    // Arbitrary query (e.g., cargo run -- soil humidity 42)
+    let args = std::env::args().collect_vec();
+    if args.len() == 4 {
+        let n = args[3].parse::<u64>().unwrap();
+        match almanac.route(&args[1], &args[2]) {
+            Some(route) => println!("{} {} is {} {}", args[1], n, args[2], route.get(&n)),
+            None => println!("No route from {} to {}", args[1], args[2]),
+        }
+    }
 }
@@ -1,10 +1,44 @@
-use std::{fs::File, io::{self, BufRead}};
+mod model;
 
-fn beat_record(time: u64, distance: u64, press_time: u64) -> bool {
-    let run_time = time - press_time;
-    let speed = press_time;
+use std::{fs::File, io::{self, BufRead}, fmt::Debug, str::FromStr};
+use num::{Integer, integer::Roots};
+use model::{RaceModel, Linear, SpeedFn, SpeedCap, ChargeDecay};
+
+fn beat_record<T: Integer + Clone>(time: &T, distance: &T, press_time: &T) -> bool {
+    let run_time = time.clone() - press_time.clone();
+    let speed = press_time.clone();
     let my_distance = speed * run_time;
-    my_distance > distance
+    my_distance > *distance
+}
+
+// Press times p with p * (time - p) > distance, i.e., strictly between the roots of
+// p^2 - time * p + distance. Returns the (inclusive) bounds, computed with integer
+// square roots only, or None if the record cannot be beaten.
+fn winning_interval<T: Integer + Roots + Clone>(time: &T, distance: &T) -> Option<(T, T)> {
+    let two = T::one() + T::one();
+    let t2 = time.clone() * time.clone();
+    let d4 = two.clone() * two.clone() * distance.clone();
+    if t2 <= d4 {
+        return None;
+    }
+
+    // The real root is within 1 from this estimate: fix it with a few steps
+    let s = (t2 - d4).sqrt();
+    let mut lo = (time.clone() - s) / two.clone();
+    while !beat_record(time, distance, &lo) {
+        // Past the maximum of the parabola: no press time can win
+        if lo.clone() * two.clone() >= *time {
+            return None;
+        }
+        lo = lo + T::one();
+    }
+    while lo > T::zero() && beat_record(time, distance, &(lo.clone() - T::one())) {
+        lo = lo - T::one();
+    }
+
+    // Symmetric around time / 2
+    let hi = time.clone() - lo.clone();
+    Some((lo, hi))
 }
 
 fn parse_input() -> Vec<(u64, u64)> {
@@ -12,27 +46,27 @@ fn parse_input() -> Vec<(u64, u64)> {
     let mut lines = io::BufReader::new(f).lines();
     
     let times = lines.next().unwrap().unwrap();
-    let times = times.split(":").nth(1).unwrap();
+    let times = times.split(':').nth(1).unwrap();
     let times = times.split_whitespace().map(|n| n.parse::<u64>().unwrap()).collect::<Vec<_>>();
 
     let distances = lines.next().unwrap().unwrap();
-    let distances = distances.split(":").nth(1).unwrap();
+    let distances = distances.split(':').nth(1).unwrap();
     let distances = distances.split_whitespace().map(|n| n.parse::<u64>().unwrap()).collect::<Vec<_>>();
 
     times.iter().zip(distances.iter()).map(|(t, d)| (*t, *d)).collect()
 }
 
-fn parse_input2() -> (u64, u64) {
+fn parse_input2<T: FromStr>() -> (T, T) where T::Err: Debug {
     let f = File::open("input/input.txt").unwrap();
     let mut lines = io::BufReader::new(f).lines();
     
     let time = lines.next().unwrap().unwrap();
-    let time = time.split(":").nth(1).unwrap();
-    let time = time.replace(" ", "").parse::<u64>().unwrap();
+    let time = time.split(':').nth(1).unwrap();
+    let time = time.replace(' ', "").parse::<T>().unwrap();
 
     let distance = lines.next().unwrap().unwrap();
-    let distance = distance.split(":").nth(1).unwrap();
-    let distance = distance.replace(" ", "").parse::<u64>().unwrap();
+    let distance = distance.split(':').nth(1).unwrap();
+    let distance = distance.replace(' ', "").parse::<T>().unwrap();
 
     (time, distance)
 }
@@ -40,15 +74,39 @@ fn parse_input2() -> (u64, u64) {
 fn main() {
     // First part
     let input = parse_input();
-    let res = input.iter().map(|(t, d)| {
-        let press_times = 1 .. *t-1;
-        press_times.filter(|press_time| beat_record(*t, *d, *press_time)).count()
-    }).product::<usize>();
+    let res = input.iter()
+        .map(|(t, d)| Linear.wins(*t, *d))
+        .product::<u64>();
     println!("Result: {}", res);
 
     // Second part
-    let (t, d) = parse_input2();
-    let press_times = 1 .. t-1;
-    let res = press_times.filter(|press_time| beat_record(t, d, *press_time)).count();
+    let (t, d) = parse_input2::<u64>();
+    let res = Linear.wins(t, d);
     println!("Result: {}", res);
+    if let Some((lo, hi)) = winning_interval(&t, &d) {
+        println!("Winning press times: {} to {}", lo, hi);
+    }
+
+    // Alternative race models: best press time for each race
+    let models: Vec<(&str, Box<dyn RaceModel>)> = vec![
+        ("linear", Box::new(Linear)),
+        ("quadratic", Box::new(SpeedFn { speed: |p| p * p / 10 })),
+        ("capped", Box::new(SpeedCap { model: Linear, max_speed: 20 })),
+        ("decay", Box::new(ChargeDecay::new(Linear, 100))),
+    ];
+    for (name, m) in models.iter() {
+        let strategies = input.iter()
+            .map(|(t, d)| model::solve(m.as_ref(), *t, *d))
+            .map(|s| format!("press {} (distance {}, margin {}, wins {})", s.press_time, s.distance, s.margin, s.wins))
+            .collect::<Vec<_>>();
+        println!("Model {}: {}", name, strategies.join(", "));
+    }
+
+    // All the models race together
+    let fleet = models.iter().map(|(_name, m)| m.as_ref()).collect::<Vec<_>>();
+    for (t, d) in input.iter() {
+        let s = model::solve_fleet(&fleet, *t, *d);
+        println!("Fleet: boat {} (press {}, margin {}), winning choices {}",
+            models[s.boat].0, s.strategy.press_time, s.strategy.margin, s.wins);
+    }
 }
@@ -0,0 +1,137 @@
+use num::BigUint;
+
+use crate::winning_interval;
+
+// A race model tells how fast the boat goes after the button has been held down.
+// By default, it follows the puzzle rules: speed grows by one for each millisecond.
+pub trait RaceModel {
+    fn speed(&self, press_time: u64) -> u64 {
+        press_time
+    }
+
+    // Distance covered in a race of the given time
+    fn distance(&self, time: u64, press_time: u64) -> u128 {
+        if press_time >= time {
+            0
+        } else {
+            self.speed(press_time) as u128 * (time - press_time) as u128
+        }
+    }
+
+    // Shortest press time with the longest distance.
+    // Tries every press time, since nothing is known about the shape of the speed:
+    // models with a closed form should override it.
+    fn best_press_time(&self, time: u64) -> u64 {
+        (0..=time)
+            .max_by_key(|p| (self.distance(time, *p), std::cmp::Reverse(*p)))
+            .unwrap()
+    }
+
+    // Number of press times that beat the record (same as above, for the closed form)
+    fn wins(&self, time: u64, record: u64) -> u64 {
+        (0..=time)
+            .filter(|p| self.distance(time, *p) > record as u128)
+            .count() as u64
+    }
+}
+
+// Puzzle rules
+pub struct Linear;
+
+impl RaceModel for Linear {
+    // p * (time - p) is maximal in the middle
+    fn best_press_time(&self, time: u64) -> u64 {
+        time / 2
+    }
+
+    fn wins(&self, time: u64, record: u64) -> u64 {
+        winning_interval(&(time as u128), &(record as u128))
+            .map_or(0, |(lo, hi)| (hi - lo + 1) as u64)
+    }
+}
+
+// Speed given by an arbitrary function of the press time
+pub struct SpeedFn<F: Fn(u64) -> u64> {
+    pub speed: F,
+}
+
+impl<F: Fn(u64) -> u64> RaceModel for SpeedFn<F> {
+    fn speed(&self, press_time: u64) -> u64 {
+        (self.speed)(press_time)
+    }
+}
+
+// The boat never goes faster than max_speed
+pub struct SpeedCap<M: RaceModel> {
+    pub model: M,
+    pub max_speed: u64,
+}
+
+impl<M: RaceModel> RaceModel for SpeedCap<M> {
+    fn speed(&self, press_time: u64) -> u64 {
+        self.model.speed(press_time).min(self.max_speed)
+    }
+}
+
+// Holding the button leaks charge: the speed drops by press_time^2 / decay
+pub struct ChargeDecay<M: RaceModel> {
+    model: M,
+    decay: u64,
+}
+
+impl<M: RaceModel> ChargeDecay<M> {
+    pub fn new(model: M, decay: u64) -> Self {
+        assert!(decay > 0, "Charge decay must be positive");
+        Self { model, decay }
+    }
+}
+
+impl<M: RaceModel> RaceModel for ChargeDecay<M> {
+    fn speed(&self, press_time: u64) -> u64 {
+        let penalty = press_time.saturating_mul(press_time) / self.decay;
+        self.model.speed(press_time).saturating_sub(penalty)
+    }
+}
+
+#[derive(Debug)]
+pub struct Strategy {
+    pub press_time: u64,
+    pub distance: u128,
+    pub margin: i128,   // Distance over the record (negative if the record is not beaten)
+    pub wins: u64,      // Number of press times that beat the record
+}
+
+pub fn solve(model: &dyn RaceModel, time: u64, record: u64) -> Strategy {
+    let press_time = model.best_press_time(time);
+    let distance = model.distance(time, press_time);
+    let wins = model.wins(time, record);
+    Strategy { press_time, distance, margin: distance as i128 - record as i128, wins }
+}
+
+#[derive(Debug)]
+pub struct FleetStrategy {
+    pub boat: usize,            // Boat with the largest margin (the first one, on ties)
+    pub strategy: Strategy,     // Its best strategy
+    pub wins: BigUint,          // Choices of one press time per boat where at least one boat beats the record
+}
+
+// Several boats race together: the team wins if any of them beats the record
+pub fn solve_fleet(boats: &[&dyn RaceModel], time: u64, record: u64) -> FleetStrategy {
+    assert!(!boats.is_empty(), "Empty fleet");
+    let strategies = boats.iter()
+        .map(|m| solve(*m, time, record))
+        .collect::<Vec<_>>();
+
+    // All the choices, minus the ones where every boat loses
+    let choices = BigUint::from(time) + 1_u32;
+    let all = boats.iter().fold(BigUint::from(1_u32), |res, _m| res * &choices);
+    let lost = strategies.iter().fold(BigUint::from(1_u32), |res, s| res * (&choices - s.wins));
+    let wins = all - lost;
+
+    let (boat, strategy) = strategies.into_iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_boat, s)| s.margin)
+        .unwrap();
+    FleetStrategy { boat, strategy, wins }
+}
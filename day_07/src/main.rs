@@ -1,180 +1,241 @@
-use std::{fs::File, io::{self, BufRead}};
+use std::{fs::File, io::{self, BufRead}, time::Instant};
 use itertools::Itertools;
 
-#[derive(PartialEq, PartialOrd, Eq, Ord)]
-enum Kind {
-    FIVE, FOUR, FULL, THREE, TWO, ONE, HIGH
+struct Category {
+    name: &'static str,
+    pattern: Vec<usize>,    // Minimum size of the largest groups of equal cards
 }
 
-#[derive(PartialEq, Eq, Debug)]
-struct Hand {
-    cards: Vec<u8>,
-    bid: usize,
-    second_part: bool,
-}
-
-impl Hand {
-    fn new(cards: &str, bid: usize, second_part: bool) -> Self {
-        Self { 
-            cards: cards.chars().map(|i| {
-                if i.is_ascii_digit() {
-                    i.to_string().parse::<u8>().unwrap()
-                } else {
-                    match i {
-                        'T' => 10,
-                        'J' => 11,
-                        'Q' => 12,
-                        'K' => 13,
-                        'A' => 14,
-                        _ => panic!("unknown card")
-                    }
-                }
-            }).collect(),
-            bid,
-            second_part,
+impl Category {
+    fn new(name: &'static str, pattern: &[usize]) -> Self {
+        Self { name, pattern: pattern.to_vec() }
+    }
+}
+
+struct Ruleset {
+    cards: Vec<char>,           // Card alphabet, from the weakest to the strongest
+    wild: Vec<u8>,              // Cards (as ranks) that can stand for any other card
+    hand_len: usize,
+    ladder: Vec<Category>,      // Categories, from the strongest to the weakest
+}
+
+impl Ruleset {
+    fn new(cards: &str, wild: &str, hand_len: usize, ladder: Vec<Category>) -> Self {
+        let cards = cards.chars().collect_vec();
+        let wild = wild.chars()
+            .map(|w| cards.iter().position(|c| *c == w).expect("unknown wild card") as u8)
+            .collect();
+        // Sort keys must fit in an u128
+        (cards.len() as u128).checked_pow(hand_len as u32)
+            .and_then(|n| n.checked_mul(ladder.len() as u128))
+            .expect("too many cards for the sort key");
+        Self { cards, wild, hand_len, ladder }
+    }
+
+    fn poker_ladder() -> Vec<Category> {
+        vec![
+            Category::new("Five of a kind", &[5]),
+            Category::new("Four of a kind", &[4]),
+            Category::new("Full house", &[3, 2]),
+            Category::new("Three of a kind", &[3]),
+            Category::new("Two pair", &[2, 2]),
+            Category::new("One pair", &[2]),
+            Category::new("High card", &[1]),
+        ]
+    }
+
+    fn standard() -> Self {
+        Self::new("23456789TJQKA", "", 5, Self::poker_ladder())
+    }
+
+    fn with_jokers() -> Self {
+        Self::new("J23456789TQKA", "J", 5, Self::poker_ladder())
+    }
+
+    fn rank(&self, card: char) -> u8 {
+        self.cards.iter().position(|c| *c == card).expect("unknown card") as u8
+    }
+
+    fn name(&self, cards: &[u8]) -> String {
+        cards.iter().map(|c| self.cards[*c as usize]).collect()
+    }
+
+    // Index in the ladder (lower is stronger).
+    // Wild cards are spread over the groups of the other cards (or form new groups)
+    // in the way that reaches the strongest category.
+    fn kind(&self, cards: &[u8]) -> usize {
+        let wild_count = cards.iter().filter(|c| self.wild.contains(c)).count();
+        let mut counts = cards.iter()
+            .filter(|c| !self.wild.contains(c))
+            .counts()
+            .into_values()
+            .collect_vec();
+        counts.extend(std::iter::repeat_n(0, wild_count));
+        self.spread(&mut counts, 0, wild_count).expect("Invalid hand")
+    }
+
+    // Best category after adding the remaining wild cards to the groups from i on
+    fn spread(&self, counts: &mut Vec<usize>, i: usize, wild_count: usize) -> Option<usize> {
+        if i + 1 >= counts.len() {
+            if let Some(last) = counts.last_mut() {
+                *last += wild_count;
+            }
+            let res = self.kind_of_counts(counts);
+            if let Some(last) = counts.last_mut() {
+                *last -= wild_count;
+            }
+            return res;
         }
+        (0..=wild_count)
+            .filter_map(|w| {
+                counts[i] += w;
+                let res = self.spread(counts, i + 1, wild_count - w);
+                counts[i] -= w;
+                res
+            })
+            .min()
     }
 
-    fn kind(&self) -> Kind {
-        let counts = self.cards.iter()
+    // Wild cards join the largest group of the other cards
+    fn greedy_kind(&self, cards: &[u8]) -> usize {
+        let wild_count = cards.iter().filter(|c| self.wild.contains(c)).count();
+        let mut counts = cards.iter()
+            .filter(|c| !self.wild.contains(c))
             .counts()
-            .values()
+            .into_values()
             .sorted()
             .rev()
-            .cloned()
-            .collect::<Vec<_>>();
-
-        let first = counts[0];
-        match first {
-            5 => Kind::FIVE,
-            4 => Kind::FOUR,
-            3 => {
-                let second = counts[1];
-                match second {
-                    2 => Kind::FULL,
-                    1 => Kind::THREE,
-                    _ => panic!("Invalid hand"),
-                }
-            },
-            2 => {
-                let second = counts[1];
-                match second {
-                    2 => Kind::TWO,
-                    1 => Kind::ONE,
-                    _ => panic!("Invalid hand")
-                }
-            },
-            1 => Kind::HIGH,
-            _ => panic!("Invalid hand")
+            .collect_vec();
+        if counts.is_empty() {
+            counts.push(0);
         }
+        counts[0] += wild_count;
+        self.kind_of_counts(&counts).expect("Invalid hand")
     }
 
-    fn kind_with_joker(&self) -> Kind {
-        let mut counts = self.cards.iter().counts();
-        let max_no_j = self.cards.iter().filter(|c| **c != 11).counts().values().cloned().max();
-        if counts.contains_key(&11) && max_no_j.is_some() {
-            let j_val = *counts.get(&11).unwrap();
-            let keys = counts.keys().cloned().collect_vec();
-            for k in keys.into_iter() {
-                if *k != 11 && *counts.get(k).unwrap() == max_no_j.unwrap() {
-                    *counts.get_mut(k).unwrap() += j_val;
-                    break;
-                }
-            }
-            *counts.get_mut(&11).unwrap() = 0;
-        }
-        
-        let counts = counts
-            .values()
+    // Index in the ladder, without wild cards
+    fn plain_kind(&self, cards: &[u8]) -> usize {
+        let counts = cards.iter().counts().into_values().collect_vec();
+        self.kind_of_counts(&counts).expect("Invalid hand")
+    }
+
+    // Index in the ladder of the groups of equal cards, if any category matches
+    fn kind_of_counts(&self, counts: &[usize]) -> Option<usize> {
+        let counts = counts.iter().sorted().rev().collect_vec();
+        self.ladder.iter()
+            .position(|cat| {
+                cat.pattern.iter()
+                    .enumerate()
+                    .all(|(i, p)| counts.get(i).is_some_and(|c| *c >= p))
+            })
+    }
+
+    // Tries every replacement of the wild cards with the other cards of the alphabet
+    fn substitute(&self, cards: &[u8]) -> Substitution {
+        let wild_positions = (0..cards.len()).filter(|i| self.wild.contains(&cards[*i])).collect_vec();
+        let replacements = (0..self.cards.len() as u8).filter(|c| !self.wild.contains(c)).collect_vec();
+        let options = if wild_positions.is_empty() {
+            vec![Vec::new()]
+        } else {
+            wild_positions.iter()
+                .map(|_| replacements.iter().cloned())
+                .multi_cartesian_product()
+                .collect_vec()
+        };
+
+        let concrete = options.into_iter().map(|option| {
+            let mut concrete = cards.to_vec();
+            wild_positions.iter().zip(option).for_each(|(i, c)| concrete[*i] = c);
+            (self.plain_kind(&concrete), concrete)
+        }).collect_vec();
+        let best_kind = concrete.iter().map(|(kind, _cards)| *kind).min().unwrap();
+        let best = concrete.into_iter()
+            .filter(|(kind, _cards)| *kind == best_kind)
+            .map(|(_kind, cards)| cards)
             .sorted()
             .rev()
-            .cloned()
-            .collect::<Vec<_>>();
-
-        let first = counts[0];
-        match first {
-            5 => Kind::FIVE,
-            4 => Kind::FOUR,
-            3 => {
-                let second = counts[1];
-                match second {
-                    2 => Kind::FULL,
-                    1 => Kind::THREE,
-                    _ => panic!("Invalid hand"),
-                }
-            },
-            2 => {
-                let second = counts[1];
-                match second {
-                    2 => Kind::TWO,
-                    1 => Kind::ONE,
-                    _ => panic!("Invalid hand")
-                }
-            },
-            1 => Kind::HIGH,
-            _ => panic!("Invalid hand")
+            .collect_vec();
+
+        Substitution {
+            cards: self.name(&best[0]),
+            category: self.ladder[best_kind].name,
+            all: best.iter().map(|c| self.name(c)).collect(),
+            greedy: self.greedy_kind(cards) == best_kind,
         }
     }
-}
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+    // Category (stronger is larger) followed by the card ranks, in base len(cards).
+    // Comparing keys is the same as comparing hands.
+    fn sort_key(&self, cards: &[u8]) -> u128 {
+        let base = self.cards.len() as u128;
+        let category = (self.ladder.len() - 1 - self.kind(cards)) as u128;
+        cards.iter().fold(category, |key, c| key * base + *c as u128)
     }
 }
 
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        let self_kind = if self.second_part { self.kind_with_joker() } else { self.kind() };
-        let other_kind = if other.second_part { other.kind_with_joker() } else { other.kind() };
+// Best replacement of the wild cards in a hand
+struct Substitution {
+    cards: String,              // Strongest concrete hand in the best category
+    category: &'static str,
+    all: Vec<String>,           // All the replacements that reach the best category
+    greedy: bool,               // The greedy rule (wild cards join the largest group) is as good
+}
 
-        let cards = if self.second_part {
-            self.cards.iter().map(|a| {
-                if *a == 11 { 1 } else { *a }
-            }).collect::<Vec<_>>()
-        } else {
-            self.cards.clone()
-        };
+impl std::fmt::Debug for Ruleset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ladder = self.ladder.iter().map(|c| c.name).join(" > ");
+        write!(f, "{} (wild: {:?}, {} cards, {})", self.cards.iter().join(""), self.wild, self.hand_len, ladder)
+    }
+}
 
-        let other_cards = if self.second_part {
-            other.cards.iter().map(|a| {
-                if *a == 11 { 1 } else { *a }
-            }).collect::<Vec<_>>()
-        } else {
-            other.cards.clone()
-        };
+#[derive(Debug)]
+struct Hand<'a> {
+    cards: Vec<u8>,     // Ranks in the ruleset alphabet
+    bid: usize,
+    key: u128,          // Precomputed sort key
+    rules: &'a Ruleset,
+}
 
-        if self_kind > other_kind {
-            std::cmp::Ordering::Less
-        } else if self_kind < other_kind {
-            std::cmp::Ordering::Greater
-        } else if cards[0] < other_cards[0] {
-            std::cmp::Ordering::Less
-        } else if cards[0] > other_cards[0] {
-            std::cmp::Ordering::Greater
-        } else if cards[1] < other_cards[1] {
-            std::cmp::Ordering::Less
-        } else if cards[1] > other_cards[1] {
-            std::cmp::Ordering::Greater
-        } else if cards[2] < other_cards[2] {
-            std::cmp::Ordering::Less
-        } else if cards[2] > other_cards[2] {
-            std::cmp::Ordering::Greater
-        } else if cards[3] < other_cards[3] {
-            std::cmp::Ordering::Less
-        } else if cards[3] > other_cards[3] {
-            std::cmp::Ordering::Greater
-        } else if cards[4] < other_cards[4] {
-            std::cmp::Ordering::Less
-        } else if cards[4] > other_cards[4] {
-            std::cmp::Ordering::Greater
-        } else {
-            std::cmp::Ordering::Equal
+impl<'a> Hand<'a> {
+    fn new(cards: &str, bid: usize, rules: &'a Ruleset) -> Self {
+        let cards = cards.chars().map(|c| rules.rank(c)).collect_vec();
+        if cards.len() != rules.hand_len {
+            panic!("Invalid hand length");
         }
+        let key = rules.sort_key(&cards);
+        Self { cards, bid, key, rules }
+    }
+
+    fn kind(&self) -> usize {
+        self.rules.kind(&self.cards)
+    }
+
+    fn substitution(&self) -> Substitution {
+        self.rules.substitute(&self.cards)
+    }
+}
+
+impl PartialEq for Hand<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Hand<'_> {}
+
+impl PartialOrd for Hand<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-fn parse_input(second_part: bool) -> Vec<Hand> {
+impl Ord for Hand<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+fn parse_input(rules: &Ruleset) -> Vec<Hand<'_>> {
     let f = File::open("input/input.txt").unwrap();
     let lines = io::BufReader::new(f).lines();
     lines.map(|line| {
@@ -182,26 +243,82 @@ fn parse_input(second_part: bool) -> Vec<Hand> {
         let mut line = line.split_whitespace();
         let cards = line.next().unwrap();
         let bid = line.next().unwrap().parse::<usize>().unwrap();
-        Hand::new(cards, bid, second_part)
+        Hand::new(cards, bid, rules)
+    }).collect_vec()
+}
+
+fn total_winnings(rules: &Ruleset) -> usize {
+    let input = parse_input(rules);
+    input.iter()
+        .sorted_unstable()
+        .enumerate()
+        .map(|(rank, hand)| (rank+1) * hand.bid)
+        .sum()
+}
+
+// Random hands from a simple xorshift generator
+fn generate_hands(rules: &Ruleset, n: usize) -> Vec<Hand<'_>> {
+    let mut state = 0x2545F4914F6CDD1D_u64;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    (0..n).map(|_| {
+        let cards = (0..rules.hand_len)
+            .map(|_| rules.cards[(random() % rules.cards.len() as u64) as usize])
+            .collect::<String>();
+        Hand::new(&cards, 0, rules)
     }).collect_vec()
 }
 
+// Sorting by precomputed keys vs. computing the kind in each comparison
+fn bench(rules: &Ruleset, n: usize) {
+    let mut hands = generate_hands(rules, n);
+    let start = Instant::now();
+    hands.sort_by(|a, b| {
+        b.kind().cmp(&a.kind())
+            .then_with(|| a.cards.cmp(&b.cards))
+    });
+    let recomputed = start.elapsed();
+
+    let mut hands_by_key = generate_hands(rules, n);
+    let start = Instant::now();
+    hands_by_key.sort_unstable_by_key(|h| h.key);
+    let by_key = start.elapsed();
+
+    assert!(hands.iter().zip(hands_by_key.iter()).all(|(a, b)| a.cards == b.cards));
+    println!("Sorting {} hands: {:?} recomputing kinds, {:?} with sort keys", n, recomputed, by_key);
+}
+
 fn main() {
     // First part
-    let input = parse_input(false);
-    let hands = input.iter().sorted().collect_vec();
-    let mut res = 0;
-    for rank in 0..hands.len() {
-        res = res + (rank+1) * hands[rank].bid
-    }
+    let res = total_winnings(&Ruleset::standard());
     println!("Result: {}", res);
 
     // Second part
-    let input = parse_input(true);
-    let hands = input.iter().sorted().collect_vec();
-    let mut res = 0;
-    for rank in 0..hands.len() {
-        res = res + (rank+1) * hands[rank].bid
-    }
+    let res = total_winnings(&Ruleset::with_jokers());
     println!("Result: {}", res);
+
+    // Joker substitutions (e.g., cargo run -- explain)
+    let args = std::env::args().collect_vec();
+    let rules = Ruleset::with_jokers();
+    let input = parse_input(&rules);
+    let substitutions = input.iter().map(|h| (h, h.substitution())).collect_vec();
+    println!("Greedy joker rule is optimal: {}", substitutions.iter().all(|(_h, s)| s.greedy));
+    if args.len() == 2 && args[1] == "explain" {
+        substitutions.iter()
+            .filter(|(h, _s)| h.cards.iter().any(|c| rules.wild.contains(c)))
+            .for_each(|(h, s)| {
+                println!("{} -> {} ({}), optimal: {}", rules.name(&h.cards), s.cards, s.category, s.all.join(" "));
+            });
+    }
+
+    // Bench (e.g., cargo run --release -- bench 1000000)
+    if args.len() == 3 && args[1] == "bench" {
+        let n = args[2].parse::<usize>().unwrap();
+        bench(&Ruleset::standard(), n);
+        bench(&Ruleset::with_jokers(), n);
+    }
 }
@@ -0,0 +1,128 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+type Network = HashMap<String, (String, String)>;
+
+fn successors<'a>(m: &'a Network, node: &str) -> Vec<&'a str> {
+    m.get(node)
+        .map(|(left, right)| vec![left.as_str(), right.as_str()])
+        .unwrap_or_default()
+}
+
+// Every node mentioned in the network, defined or not
+fn nodes(m: &Network) -> BTreeSet<&str> {
+    m.iter()
+        .flat_map(|(source, (left, right))| [source.as_str(), left.as_str(), right.as_str()])
+        .collect()
+}
+
+// Nodes reachable from start (start included)
+pub fn reachable<'a>(m: &'a Network, start: &'a str) -> HashSet<&'a str> {
+    let mut visited = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for next in successors(m, node) {
+            if visited.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    visited
+}
+
+// Strongly connected components (Kosaraju), each one sorted
+pub fn components(m: &Network) -> Vec<Vec<&str>> {
+    let nodes = nodes(m);
+
+    // First pass: nodes in order of completion of a DFS
+    let mut visited = HashSet::new();
+    let mut order = Vec::new();
+    for node in nodes.iter() {
+        if !visited.insert(*node) {
+            continue;
+        }
+        let mut stack = vec![(*node, 0)];
+        while let Some((node, i)) = stack.pop() {
+            let next = successors(m, node);
+            if i < next.len() {
+                stack.push((node, i + 1));
+                if visited.insert(next[i]) {
+                    stack.push((next[i], 0));
+                }
+            } else {
+                order.push(node);
+            }
+        }
+    }
+
+    // Second pass: DFS on the reversed graph, in reverse order of completion
+    let mut reversed: HashMap<&str, Vec<&str>> = HashMap::new();
+    for node in nodes.iter() {
+        for next in successors(m, node) {
+            reversed.entry(next).or_default().push(node);
+        }
+    }
+    let mut assigned = HashSet::new();
+    let mut res = Vec::new();
+    for node in order.into_iter().rev() {
+        if !assigned.insert(node) {
+            continue;
+        }
+        let mut component = vec![node];
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            for prev in reversed.get(node).into_iter().flatten() {
+                if assigned.insert(prev) {
+                    component.push(prev);
+                    stack.push(prev);
+                }
+            }
+        }
+        component.sort();
+        res.push(component);
+    }
+    res.sort();
+    res
+}
+
+// Nodes from which no node ending with Z can be reached
+pub fn never_reach_z(m: &Network) -> Vec<&str> {
+    let mut reversed: HashMap<&str, Vec<&str>> = HashMap::new();
+    for node in nodes(m) {
+        for next in successors(m, node) {
+            reversed.entry(next).or_default().push(node);
+        }
+    }
+    let mut visited = nodes(m).into_iter().filter(|n| n.ends_with('Z')).collect::<HashSet<_>>();
+    let mut queue = visited.iter().cloned().collect::<VecDeque<_>>();
+    while let Some(node) = queue.pop_front() {
+        for prev in reversed.get(node).into_iter().flatten() {
+            if visited.insert(prev) {
+                queue.push_back(prev);
+            }
+        }
+    }
+    nodes(m).into_iter().filter(|n| !visited.contains(n)).collect()
+}
+
+// Nodes used as left or right destinations, but never defined
+pub fn dead_references(m: &Network) -> Vec<&str> {
+    nodes(m).into_iter().filter(|n| !m.contains_key(*n)).collect()
+}
+
+// Graphviz representation, with L/R labels on the edges
+pub fn to_dot(m: &Network) -> String {
+    let mut res = String::from("digraph network {\n");
+    for node in nodes(m) {
+        if node.ends_with('A') || node.ends_with('Z') {
+            res.push_str(&format!("    \"{}\" [style=filled];\n", node));
+        }
+    }
+    for node in nodes(m) {
+        if let Some((left, right)) = m.get(node) {
+            res.push_str(&format!("    \"{}\" -> \"{}\" [label=\"L\"];\n", node, left));
+            res.push_str(&format!("    \"{}\" -> \"{}\" [label=\"R\"];\n", node, right));
+        }
+    }
+    res.push_str("}\n");
+    res
+}
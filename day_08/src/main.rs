@@ -1,4 +1,7 @@
-use std::{fs::File, io::{self, BufRead}, collections::HashMap};
+mod analysis;
+
+use std::{fs::{self, File}, io::{self, BufRead}, collections::{BTreeSet, HashMap}};
+use num::{BigInt, Integer, Zero};
 
 fn parse_input() -> (String, HashMap<String, (String, String)>) {
     let f = File::open("input/input.txt").unwrap();
@@ -23,83 +26,229 @@ fn parse_input() -> (String, HashMap<String, (String, String)>) {
     (directions, m)
 }
 
-fn main() {
-    let (directions, m) = parse_input();
+// Network with the nodes interned to dense ids and the directions compiled
+// to a tape (0 for left, 1 for right).
+// Nodes that only appear as destinations get an id too, without outgoing edges.
+struct CompiledNetwork {
+    names: Vec<String>,
+    next: Vec<Option<[u32; 2]>>,    // Left and right destination of each node (None if undefined)
+    tape: Vec<u8>,
+    jump: Vec<Option<u32>>,         // Node reached after a full pass of the tape (None if stuck before)
+    z_hits: Vec<Vec<(usize, u32)>>, // Nodes ending with Z visited during a full pass, with their position
+}
 
-    // First part
-    let mut count = 0;
-    let mut source = &String::from("AAA");
-    loop {
-        let d = directions.chars().nth(count % directions.len()).unwrap();
-        let (left, right) = m.get(source).unwrap();
-        match d {
-            'L' => {
-                source = left;
-            }
-            'R' => {
-                source = right;
-            }
-            _ => panic!("Unknown direction")
+impl CompiledNetwork {
+    fn new(directions: &str, m: &HashMap<String, (String, String)>) -> Self {
+        let names = m.iter()
+            .flat_map(|(name, (left, right))| [name, left, right])
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let ids = names.iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i as u32))
+            .collect::<HashMap<_, _>>();
+        let next = names.iter()
+            .map(|name| m.get(name).map(|(left, right)| [ids[left.as_str()], ids[right.as_str()]]))
+            .collect::<Vec<_>>();
+        let tape = directions.chars()
+            .map(|d| match d {
+                'L' => 0,
+                'R' => 1,
+                _ => panic!("Unknown direction")
+            })
+            .collect::<Vec<_>>();
+
+        let mut network = Self { names, next, tape, jump: Vec::new(), z_hits: Vec::new() };
+        for node in 0..network.names.len() as u32 {
+            let pass = network.pass(node);
+            let complete = pass.len() == network.tape.len();
+            network.jump.push(complete.then(|| pass.last().copied().unwrap_or(node)));
+            network.z_hits.push(pass.into_iter()
+                .enumerate()
+                .filter(|(_i, n)| network.names[*n as usize].ends_with('Z'))
+                .collect());
         }
-        count += 1;
-        if *source == "ZZZ" {
-            break;
+        network
+    }
+
+    fn id(&self, name: &str) -> u32 {
+        self.names.binary_search_by(|n| n.as_str().cmp(name)).expect("Unknown node") as u32
+    }
+
+    // None if node is undefined
+    fn step(&self, node: u32, count: usize) -> Option<u32> {
+        self.next[node as usize].map(|next| next[self.tape[count % self.tape.len()] as usize])
+    }
+
+    // Nodes visited during a full pass of the tape (stops at undefined nodes)
+    fn pass(&self, node: u32) -> Vec<u32> {
+        let mut res = Vec::with_capacity(self.tape.len());
+        let mut node = node;
+        for count in 0..self.tape.len() {
+            match self.step(node, count) {
+                Some(next) => node = next,
+                None => break,
+            }
+            res.push(node);
         }
+        res
     }
-    println!("Count: {}", count);
 
-    // Second part
-    let sources = m.keys().filter(|k| k.ends_with("A")).collect::<Vec<_>>();
-    let first_z = sources.iter().map(|s| {
-        let initial_source = (*s).clone();
-        let mut source = &initial_source;
+    // Number of steps until reaching target (a node ending with Z).
+    // Passes that do not visit target are skipped using the jump table.
+    fn walk(&self, start: u32, target: u32) -> Option<usize> {
+        assert!(self.names[target as usize].ends_with('Z'), "The target must end with Z");
+        let mut node = start;
         let mut count = 0;
-        loop {
-            let d = directions.chars().nth(count % directions.len()).unwrap();
-            let (left, right) = m.get(source).unwrap();
-            match d {
-                'L' => {
-                    source = left;
-                }
-                'R' => {
-                    source = right;
-                }
-                _ => panic!("Unknown direction")
-            }
-            count += 1;
-            if source.ends_with("Z") {
-                break;
+        // After visiting every node at the start of a pass, the walk is in a loop
+        for _ in 0..=self.names.len() {
+            if let Some((i, _n)) = self.z_hits[node as usize].iter().find(|(_i, n)| *n == target) {
+                return Some(count + i + 1);
             }
+            node = self.jump[node as usize]?;
+            count += self.tape.len();
         }
-        count
-    }).collect::<Vec<_>>();
-    let count = first_z.into_iter().reduce(|a, b| num::integer::lcm(a, b)).unwrap();
-    println!("Count: {}", count);
-
-    // Second part (brute force)
-    let mut count = 0;
-    let mut sources = m.keys().filter(|k| k.ends_with("A")).collect::<Vec<_>>();
-    loop {
-        let d = directions.chars().nth(count % directions.len()).unwrap();
-        for i in 0..sources.len() {
-            let mut source = *sources.get(i).unwrap();
-            let (left, right) = m.get(source).unwrap();
-            match d {
-                'L' => {
-                    source = left;
-                }
-                'R' => {
-                    source = right;
+        None
+    }
+}
+
+// Walk of a ghost: after the first `start` steps, it repeats every `period` steps.
+// If the ghost reaches an undefined node, there is no period and the walk ends after `start` steps.
+struct Cycle {
+    start: usize,
+    period: Option<usize>,
+    z_steps: Vec<usize>,    // Steps (before start + period) on a node ending with Z
+}
+
+impl Cycle {
+    // The state at the start of a pass is just the node (the tape is at its beginning),
+    // so the walk goes one pass at a time through the jump table
+    fn new(network: &CompiledNetwork, source: u32) -> Self {
+        let len = network.tape.len();
+        let mut seen = vec![usize::MAX; network.names.len()];
+        let mut z_steps = Vec::new();
+        if network.names[source as usize].ends_with('Z') {
+            z_steps.push(0);
+        }
+        let mut node = source;
+        let mut pass = 0;
+        loop {
+            if seen[node as usize] != usize::MAX {
+                let start = seen[node as usize] * len;
+                let period = (pass - seen[node as usize]) * len;
+                z_steps.retain(|z| *z < start + period);
+                return Self { start, period: Some(period), z_steps };
+            }
+            seen[node as usize] = pass;
+            z_steps.extend(network.z_hits[node as usize].iter().map(|(i, _n)| pass * len + i + 1));
+            match network.jump[node as usize] {
+                Some(next) => node = next,
+                // Stuck on an undefined node during this pass (its Z status is already in z_hits)
+                None => {
+                    let start = pass * len + network.pass(node).len() + 1;
+                    return Self { start, period: None, z_steps };
                 }
-                _ => panic!("Unknown direction")
             }
-            sources[i] = source;
-        }
-        count += 1;
-        if sources.iter().all(|s| s.ends_with("Z")) {
-            break;
+            pass += 1;
         }
     }
-    println!("Count: {}", count);
 
+    fn is_z(&self, step: usize) -> bool {
+        let step = match self.period {
+            _ if step < self.start => step,
+            Some(period) => self.start + (step - self.start) % period,
+            None => return false,
+        };
+        self.z_steps.binary_search(&step).is_ok()
+    }
+}
+
+// Generalized Chinese Remainder Theorem: x = a1 (mod m1) and x = a2 (mod m2)
+// become a single x = a (mod lcm(m1, m2)), if the two are compatible.
+// The moduli grow with every ghost, hence the big integers.
+fn crt((a1, m1): &(BigInt, BigInt), (a2, m2): &(BigInt, BigInt)) -> Option<(BigInt, BigInt)> {
+    let e = m1.extended_gcd(m2);
+    let diff = a2 - a1;
+    if !(&diff % &e.gcd).is_zero() {
+        return None;
+    }
+    let lcm = m1 / &e.gcd * m2;
+    let k = (diff / &e.gcd * &e.x).mod_floor(&(m2 / &e.gcd));
+    Some(((a1 + m1 * k).mod_floor(&lcm), lcm))
+}
+
+// First step (after the start) where all the ghosts are on a Z node, if any
+fn synchronize(cycles: &[Cycle]) -> Option<BigInt> {
+    // A ghost that reaches an undefined node: only the steps before can work
+    if let Some(end) = cycles.iter().filter(|c| c.period.is_none()).map(|c| c.start).min() {
+        return (1..end).find(|s| cycles.iter().all(|c| c.is_z(*s))).map(BigInt::from);
+    }
+
+    // Before every ghost enters its cycle: check the steps one by one
+    let transient = cycles.iter().map(|c| c.start).max().unwrap();
+    if let Some(step) = (1..transient).find(|s| cycles.iter().all(|c| c.is_z(*s))) {
+        return Some(BigInt::from(step));
+    }
+
+    // Then each ghost is on a Z node at fixed offsets modulo its period
+    let mut solutions = vec![(BigInt::zero(), BigInt::from(1))];
+    for c in cycles.iter() {
+        let period = BigInt::from(c.period.unwrap());
+        let offsets = c.z_steps.iter()
+            .filter(|z| **z >= c.start)
+            .map(|z| (BigInt::from(*z) % &period, period.clone()))
+            .collect::<Vec<_>>();
+        solutions = solutions.iter()
+            .flat_map(|s| offsets.iter().filter_map(|o| crt(s, o)))
+            .collect();
+    }
+
+    // Smallest solution not before all the cycles have started
+    let transient = BigInt::from(transient.max(1));
+    solutions.into_iter()
+        .map(|(a, m)| &transient + (a - &transient).mod_floor(&m))
+        .min()
+}
+
+fn main() {
+    let (directions, m) = parse_input();
+
+    let network = CompiledNetwork::new(&directions, &m);
+
+    // First part
+    let zzz = network.id("ZZZ");
+    match network.walk(network.id("AAA"), zzz) {
+        Some(count) => println!("Count: {}", count),
+        None => println!("ZZZ is never reached"),
+    }
+
+    // Second part
+    let cycles = network.names.iter()
+        .enumerate()
+        .filter(|(id, name)| name.ends_with('A') && network.next[*id].is_some())
+        .map(|(id, _name)| Cycle::new(&network, id as u32))
+        .collect::<Vec<_>>();
+    match synchronize(&cycles) {
+        Some(count) => println!("Count: {}", count),
+        None => println!("The ghosts never meet on Z nodes"),
+    }
+
+    // Network analysis (e.g., cargo run -- dot network.dot)
+    let mut starts = m.keys().filter(|k| k.ends_with('A')).collect::<Vec<_>>();
+    starts.sort();
+    for s in starts {
+        println!("Nodes reachable from {}: {}", s, analysis::reachable(&m, s).len());
+    }
+    let components = analysis::components(&m);
+    println!("Strongly connected components: {} ({} with more than one node)",
+        components.len(), components.iter().filter(|c| c.len() > 1).count());
+    println!("Nodes that never reach Z: {}", analysis::never_reach_z(&m).len());
+    println!("Dead references: {:?}", analysis::dead_references(&m));
+
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.len() == 3 && args[1] == "dot" {
+        fs::write(&args[2], analysis::to_dot(&m)).unwrap();
+    }
 }
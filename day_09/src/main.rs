@@ -1,5 +1,6 @@
 use std::{fs::File, io::{self, BufRead}};
 use itertools::Itertools;
+use num::{BigInt, BigRational, One, Zero};
 
 fn parse_input() -> Vec<Vec<i64>> {
     let f = File::open("input/input.txt").unwrap();
@@ -10,57 +11,158 @@ fn parse_input() -> Vec<Vec<i64>> {
     }).collect_vec()
 }
 
-fn compute_differences(v: Vec<i64>) -> Vec<Vec<i64>> {
-    let mut res = Vec::new();
-    let mut new = &v;
-    res.push(v.clone());
+// The differences never reach a row of zeros: with n values, only polynomials of
+// degree up to n - 2 can be recognized (one more value is needed to check them)
+#[derive(Debug)]
+struct NotPolynomial {
+    max_degree: usize,
+    residuals: Vec<Vec<BigInt>>,    // All the rows of differences
+}
+
+impl std::fmt::Display for NotPolynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Sequence is not a polynomial of degree at most {}", self.max_degree)?;
+        for (i, row) in self.residuals.iter().enumerate() {
+            writeln!(f, "{}{}", " ".repeat(2 * i), row.iter().join("   "))?;
+        }
+        Ok(())
+    }
+}
+
+// Big integers: each row of differences can double the largest value
+fn compute_differences(v: Vec<i64>) -> Result<Vec<Vec<BigInt>>, NotPolynomial> {
+    let max_degree = v.len().saturating_sub(2);
+    let mut res = vec![v.into_iter().map(BigInt::from).collect_vec()];
     loop {
-        let next = new.iter().tuple_windows().map(|(a, b)| *b - *a).collect_vec();
-        res.push(next);
-        new = res.last().unwrap();
-        if new.iter().all(|x| *x == 0) {
-            break;
+        let new = res.last().unwrap();
+        if new.is_empty() {
+            return Err(NotPolynomial { max_degree, residuals: res });
         }
+        if new.iter().all(|x| x.is_zero()) {
+            return Ok(res);
+        }
+        let next = new.iter().tuple_windows().map(|(a, b)| b - a).collect_vec();
+        res.push(next);
     }
-    res
 }
 
-fn compute_last_value(v: Vec<Vec<i64>>) -> i64 {
-    let mut res = 0;
+fn compute_last_value(v: &[Vec<BigInt>]) -> BigInt {
+    let mut res = BigInt::zero();
     for i in (0..v.len()).rev() {
         let inner = v.get(i).unwrap();
         let last_val = inner.last().unwrap();
-        res += *last_val;
+        res += last_val;
     }
     res
 }
 
-fn compute_first_value(v: Vec<Vec<i64>>) -> i64 {
-    let mut res = 0;
+fn compute_first_value(v: &[Vec<BigInt>]) -> BigInt {
+    let mut res = BigInt::zero();
     for i in (0..v.len()).rev() {
         let inner = v.get(i).unwrap();
         let first_val = inner.first().unwrap();
-        res = *first_val - res;
+        res = first_val - res;
     }
     res
 }
 
+// Polynomial through the sequence in Newton forward-difference form:
+// f(x) = sum of newton[k] * binomial(x, k), with f(i) the i-th value (from 0)
+struct Polynomial {
+    newton: Vec<BigInt>,
+}
+
+impl Polynomial {
+    fn new(differences: &[Vec<BigInt>]) -> Self {
+        let mut newton = differences.iter()
+            .filter_map(|row| row.first())
+            .cloned()
+            .collect_vec();
+        while newton.len() > 1 && newton.last().unwrap().is_zero() {
+            newton.pop();
+        }
+        Self { newton }
+    }
+
+    fn degree(&self) -> usize {
+        self.newton.len() - 1
+    }
+
+    // Works for any integer, including far away and negative indexes
+    fn eval(&self, x: &BigInt) -> BigInt {
+        let mut res = BigInt::zero();
+        let mut binomial = BigInt::one();
+        for (k, c) in self.newton.iter().enumerate() {
+            res += c * &binomial;
+            binomial = binomial * (x - k) / (k + 1);
+        }
+        res
+    }
+
+    // Coefficients in the power basis, from the constant term
+    fn coefficients(&self) -> Vec<BigRational> {
+        let mut res = vec![BigRational::zero(); self.newton.len()];
+        // x (x - 1) ... (x - k + 1) / k!
+        let mut falling = vec![BigRational::one()];
+        for (k, c) in self.newton.iter().enumerate() {
+            for (i, f) in falling.iter().enumerate() {
+                res[i] += f * BigRational::from(c.clone());
+            }
+            let k = BigRational::from(BigInt::from(k));
+            let mut next = vec![BigRational::zero(); falling.len() + 1];
+            for (i, f) in falling.iter().enumerate() {
+                next[i + 1] += f / (&k + BigRational::one());
+                next[i] -= f * &k / (&k + BigRational::one());
+            }
+            falling = next;
+        }
+        res
+    }
+}
+
 fn main() {
-    // First part
     let input = parse_input();
-    let res = input.into_iter()
-        .map(|v| compute_differences(v))
+    // A single sequence that is not a polynomial makes both sums meaningless: stop there
+    let differences = input.into_iter()
+        .enumerate()
+        .map(|(i, v)| compute_differences(v).map_err(|e| (i + 1, e)))
+        .collect::<Result<Vec<_>, _>>();
+    let differences = match differences {
+        Ok(differences) => differences,
+        Err((line, e)) => {
+            eprint!("Line {}: {}", line, e);
+            std::process::exit(1);
+        }
+    };
+
+    // First part
+    let res = differences.iter()
         .map(|v| compute_last_value(v))
-        .sum::<i64>();
+        .sum::<BigInt>();
 
     println!("Sum: {}", res);
 
     // Second part
-    let input = parse_input();
-    let res = input.into_iter()
-        .map(|v| compute_differences(v))
+    let res = differences.iter()
         .map(|v| compute_first_value(v))
-        .sum::<i64>();
+        .sum::<BigInt>();
+
+    println!("Sum: {}", res);
 
-    print!("Sum: {}", res);   
+    // Polynomial model (e.g., cargo run -- 1000000 evaluates all the sequences there)
+    let polynomials = differences.iter()
+        .map(|v| Polynomial::new(v))
+        .collect_vec();
+    if let Some(max_degree) = polynomials.iter().map(|p| p.degree()).max() {
+        println!("Max degree: {}", max_degree);
+    }
+    if let Some(first) = polynomials.first() {
+        println!("First sequence: degree {}, coefficients {}", first.degree(), first.coefficients().iter().join(" "));
+    }
+
+    if let Some(x) = std::env::args().nth(1) {
+        let x = x.parse::<BigInt>().unwrap();
+        let res = polynomials.iter().map(|p| p.eval(&x)).sum::<BigInt>();
+        println!("Sum at {}: {}", x, res);
+    }
 }
\ No newline at end of file
@@ -1,8 +1,11 @@
-use std::{fs::File, io::{self, BufRead}, collections::HashMap};
+mod render;
+
+use std::{fs::{self, File}, io::{self, BufRead}, collections::{HashMap, HashSet}};
 use Pipe::{NS, EW, NE, NW, SE, SW, G, START};
 use Dir::{N, S, E, W};
 use itertools::Itertools;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(PartialEq, Eq, Clone, Copy)]
 enum Pipe {
     NS, EW, NE, NW, SE, SW, G, START
@@ -115,63 +118,62 @@ fn parse_input() -> (Vec<Vec<Pipe>>, (usize, usize)) {
 
     let mut all_lines = Vec::new();
     all_lines.push(first_line);
-    all_lines.extend(input.into_iter());
+    all_lines.extend(input);
     all_lines.push(last_line);
 
     (all_lines, s)
 }
 
-fn main() {
-    let (pipes, s) = parse_input();
-
-    // First part
-    let mut paths = vec![
-        Some(move_n(s)), 
-        Some(move_s(s)), 
-        Some(move_e(s)),
-        Some(move_w(s))
-    ];
-    let mut steps = 1;
-    loop {
-        paths = paths.into_iter().map(|o| 
-            o.map(|((x, y), dir)| {
-                next((x,y), pipes[y][x], dir)
-            }
-        )).flatten().collect();
-        if paths.iter().any(|o| o.is_some_and(|(pos, _)| pos == s)) {
-            break;
+// Actual pipe under S, from the neighbours that connect to it
+fn infer_start(pipes: &[Vec<Pipe>], (x, y): (usize, usize)) -> Result<Pipe, String> {
+    let n = matches!(pipes[y-1][x], NS | SE | SW);
+    let s = matches!(pipes[y+1][x], NS | NE | NW);
+    let e = matches!(pipes[y][x+1], EW | NW | SW);
+    let w = matches!(pipes[y][x-1], EW | NE | SE);
+    match (n, s, e, w) {
+        (true, true, false, false) => Ok(NS),
+        (false, false, true, true) => Ok(EW),
+        (true, false, true, false) => Ok(NE),
+        (true, false, false, true) => Ok(NW),
+        (false, true, true, false) => Ok(SE),
+        (false, true, false, true) => Ok(SW),
+        _ => {
+            let connections = [n, s, e, w].iter().filter(|c| **c).count();
+            Err(format!("S has {} valid connections, expected 2", connections))
         }
-        steps += 1;
     }
+}
 
-    println!("Loop len: {}", steps);
-    println!("Furthest point: {}", (steps+1)/2);
-
-    // Second part
-    let mut path = Some(move_n(s));
-    let mut path_points = Vec::new();
-    loop {
-        let ((x, y), _) = path.unwrap();
-        path_points.push((x, y));
-        path = path.map(|((x, y), dir)| 
-            next((x,y), pipes[y][x], dir)
-        ).flatten();
-        if let Some((pos, _)) = path {
-            if pos == s {
-                path_points.push(pos);
-                break;
-            }
+// Positions of the loop in order, starting from S.
+// The pipe under S must be already known.
+fn trace_loop(pipes: &[Vec<Pipe>], s: (usize, usize)) -> Vec<(usize, usize)> {
+    let mut path = match pipes[s.1][s.0] {
+        NS | NE | NW => Some(move_n(s)),
+        SE | SW => Some(move_s(s)),
+        EW => Some(move_e(s)),
+        _ => panic!("Unexpected pipe under S"),
+    };
+    let mut path_points = vec![s];
+    while let Some(((x, y), dir)) = path {
+        if (x, y) == s {
+            return path_points;
         }
+        path_points.push((x, y));
+        path = next((x, y), pipes[y][x], dir);
     }
+    panic!("Broken loop");
+}
 
-    let path_points = path_points.into_iter()
+// Scanline: a tile is inside if there is an odd number of vertical crossings on its left
+fn count_inner_scanline(pipes: &[Vec<Pipe>], path_points: &[(usize, usize)]) -> usize {
+    let path_points = path_points.iter()
         .into_group_map_by(|(_x, y)| *y)
         .into_iter()
         .map(|(k, v)| {
             (
                 k,
                 v.into_iter()
-                    .map(|(x, y)| (x, pipes[y][x]))
+                    .map(|(x, y)| (*x, pipes[*y][*x]))
                     .collect::<HashMap<_,_>>()
             )
         }).collect::<HashMap<_,_>>();
@@ -183,11 +185,108 @@ fn main() {
         for x in 0..x_max {
             if inside && !points_dirs.contains_key(&x) {
                 inner_count += 1;
-            } else if points_dirs.contains_key(&x) && (points_dirs[&x] == NS || points_dirs[&x] == NW || points_dirs[&x] == NE || points_dirs[&x] == START) {
+            } else if points_dirs.contains_key(&x) && (points_dirs[&x] == NS || points_dirs[&x] == NW || points_dirs[&x] == NE) {
                 inside = !inside;
             }
         }
     });
 
+    inner_count
+}
+
+// Shoelace formula for the area of the loop, then Pick's theorem:
+// area = inner + boundary / 2 - 1
+fn count_inner_area(path_points: &[(usize, usize)]) -> usize {
+    let double_area = path_points.iter()
+        .circular_tuple_windows()
+        .map(|((x1, y1), (x2, y2))| (*x1 as i64) * (*y2 as i64) - (*x2 as i64) * (*y1 as i64))
+        .sum::<i64>()
+        .abs();
+    ((double_area - path_points.len() as i64) / 2 + 1) as usize
+}
+
+// 3x3 drawing of a pipe: true where the pipe is
+fn stencil(pipe: Pipe) -> [[bool; 3]; 3] {
+    let (n, s, e, w) = match pipe {
+        NS => (true, true, false, false),
+        EW => (false, false, true, true),
+        NE => (true, false, true, false),
+        NW => (true, false, false, true),
+        SE => (false, true, true, false),
+        SW => (false, true, false, true),
+        _ => return [[false; 3]; 3],
+    };
+    [
+        [false, n, false],
+        [w, true, e],
+        [false, s, false],
+    ]
+}
+
+// Flood fill from the outside on the grid scaled up 3 times, so that it can squeeze
+// between adjacent pipes. Returns the tiles (not on the loop) reachable from the outside.
+fn squeeze_fill(pipes: &[Vec<Pipe>], path_points: &[(usize, usize)]) -> HashSet<(usize, usize)> {
+    let (x_max, y_max) = (pipes.first().unwrap().len(), pipes.len());
+    let mut walls = vec![vec![false; 3 * x_max]; 3 * y_max];
+    for (x, y) in path_points.iter() {
+        for (dy, row) in stencil(pipes[*y][*x]).iter().enumerate() {
+            for (dx, wall) in row.iter().enumerate() {
+                walls[3 * y + dy][3 * x + dx] = *wall;
+            }
+        }
+    }
+
+    // The border of the grid is ground, so (0, 0) is outside
+    let mut reached = vec![vec![false; 3 * x_max]; 3 * y_max];
+    reached[0][0] = true;
+    let mut stack = vec![(0_usize, 0_usize)];
+    while let Some((x, y)) = stack.pop() {
+        let neighbours = [(x + 1, y), (x, y + 1), (x.wrapping_sub(1), y), (x, y.wrapping_sub(1))];
+        for (nx, ny) in neighbours {
+            if nx < 3 * x_max && ny < 3 * y_max && !walls[ny][nx] && !reached[ny][nx] {
+                reached[ny][nx] = true;
+                stack.push((nx, ny));
+            }
+        }
+    }
+
+    let on_loop = path_points.iter().collect::<HashSet<_>>();
+    (0..y_max)
+        .cartesian_product(0..x_max)
+        .map(|(y, x)| (x, y))
+        .filter(|(x, y)| !on_loop.contains(&(*x, *y)) && reached[3 * y + 1][3 * x + 1])
+        .collect()
+}
+
+fn main() {
+    let (mut pipes, s) = parse_input();
+    match infer_start(&pipes, s) {
+        Ok(pipe) => pipes[s.1][s.0] = pipe,
+        Err(e) => panic!("{}", e),
+    }
+
+    // First part
+    let path_points = trace_loop(&pipes, s);
+    let steps = path_points.len();
+
+    println!("Loop len: {}", steps);
+    println!("Furthest point: {}", steps/2);
+
+    // Second part
+    let inner_count = count_inner_area(&path_points);
+    assert_eq!(inner_count, count_inner_scanline(&pipes, &path_points), "Scanline and area disagree");
+    let outside = squeeze_fill(&pipes, &path_points);
+    let tiles = pipes.len() * pipes.first().unwrap().len();
+    assert_eq!(inner_count, tiles - path_points.len() - outside.len(), "Flood fill and area disagree");
+
     println!("Inner tiles: {}", inner_count);
+
+    // Rendering (e.g., cargo run -- render, or cargo run -- html maze.html)
+    let args = std::env::args().collect_vec();
+    let path_points = path_points.into_iter().collect::<HashSet<_>>();
+    match args.get(1).map(|a| a.as_str()) {
+        Some("render") => print!("{}", render::to_terminal(&pipes, &path_points, &outside)),
+        Some("html") => fs::write(&args[2], render::to_html(&pipes, &path_points, &outside)).unwrap(),
+        _ => {}
+    }
 }
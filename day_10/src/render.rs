@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+
+use crate::Pipe::{self, NS, EW, NE, NW, SE, SW};
+
+#[derive(PartialEq, Clone, Copy)]
+enum Tile {
+    Loop,
+    Inside,
+    Outside,
+}
+
+fn glyph(pipe: Pipe) -> char {
+    match pipe {
+        NS => '│',
+        EW => '─',
+        NE => '└',
+        NW => '┘',
+        SE => '┌',
+        SW => '┐',
+        _ => '·',
+    }
+}
+
+// Tiles of the grid, without the ground border added by parse_input
+fn tiles<'a>(pipes: &'a [Vec<Pipe>], path_points: &'a HashSet<(usize, usize)>, outside: &'a HashSet<(usize, usize)>)
+    -> impl Iterator<Item = Vec<(char, Tile, bool)>> + 'a {
+    (1..pipes.len()-1).map(move |y| {
+        (1..pipes[y].len()-1).map(|x| {
+            let tile = if path_points.contains(&(x, y)) {
+                Tile::Loop
+            } else if outside.contains(&(x, y)) {
+                Tile::Outside
+            } else {
+                Tile::Inside
+            };
+            (glyph(pipes[y][x]), tile, pipes[y][x] != Pipe::G)
+        }).collect()
+    })
+}
+
+// Main loop in bold yellow, junk pipes dimmed, inside green and outside blue
+pub fn to_terminal(pipes: &[Vec<Pipe>], path_points: &HashSet<(usize, usize)>, outside: &HashSet<(usize, usize)>) -> String {
+    let mut res = String::new();
+    for row in tiles(pipes, path_points, outside) {
+        for (c, tile, is_pipe) in row {
+            let style = match (tile, is_pipe) {
+                (Tile::Loop, _) => "1;33",
+                (Tile::Inside, true) => "2;32",
+                (Tile::Inside, false) => "32",
+                (Tile::Outside, true) => "2;34",
+                (Tile::Outside, false) => "34",
+            };
+            res.push_str(&format!("\x1b[{}m{}", style, c));
+        }
+        res.push_str("\x1b[0m\n");
+    }
+    res
+}
+
+pub fn to_html(pipes: &[Vec<Pipe>], path_points: &HashSet<(usize, usize)>, outside: &HashSet<(usize, usize)>) -> String {
+    let mut res = String::from(concat!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\n",
+        "body { background: #111; }\n",
+        "pre { font-family: monospace; line-height: 1; }\n",
+        ".loop { color: #ffd700; font-weight: bold; }\n",
+        ".junk { opacity: 0.4; }\n",
+        ".inside { color: #3c3; }\n",
+        ".outside { color: #48f; }\n",
+        "</style>\n</head>\n<body>\n<pre>\n",
+    ));
+    for row in tiles(pipes, path_points, outside) {
+        for (c, tile, is_pipe) in row {
+            let class = match (tile, is_pipe) {
+                (Tile::Loop, _) => "loop",
+                (Tile::Inside, true) => "inside junk",
+                (Tile::Inside, false) => "inside",
+                (Tile::Outside, true) => "outside junk",
+                (Tile::Outside, false) => "outside",
+            };
+            res.push_str(&format!("<span class=\"{}\">{}</span>", class, c));
+        }
+        res.push('\n');
+    }
+    res.push_str("</pre>\n</body>\n</html>\n");
+    res
+}
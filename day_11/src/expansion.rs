@@ -0,0 +1,53 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Axis {
+    Row,
+    Column,
+}
+
+// How many lines an empty line becomes after the expansion.
+// run is the number of consecutive empty lines it belongs to.
+pub trait Expansion {
+    fn size(&self, axis: Axis, index: usize, run: usize) -> u128;
+}
+
+// Puzzle rules: every empty line becomes the same number of lines
+pub struct Uniform(pub u64);
+
+impl Expansion for Uniform {
+    fn size(&self, _axis: Axis, _index: usize, _run: usize) -> u128 {
+        self.0 as u128
+    }
+}
+
+// Different factors for rows and columns
+pub struct PerAxis {
+    pub rows: u64,
+    pub columns: u64,
+}
+
+impl Expansion for PerAxis {
+    fn size(&self, axis: Axis, _index: usize, _run: usize) -> u128 {
+        match axis {
+            Axis::Row => self.rows as u128,
+            Axis::Column => self.columns as u128,
+        }
+    }
+}
+
+// The factor depends on the number of consecutive empty lines
+pub struct ByRun<F: Fn(usize) -> u64>(pub F);
+
+impl<F: Fn(usize) -> u64> Expansion for ByRun<F> {
+    fn size(&self, _axis: Axis, _index: usize, run: usize) -> u128 {
+        (self.0)(run) as u128
+    }
+}
+
+// Arbitrary factor for each empty line
+pub struct PerIndex<F: Fn(Axis, usize) -> u64>(pub F);
+
+impl<F: Fn(Axis, usize) -> u64> Expansion for PerIndex<F> {
+    fn size(&self, axis: Axis, index: usize, _run: usize) -> u128 {
+        (self.0)(axis, index) as u128
+    }
+}
@@ -1,25 +1,53 @@
-use std::{fs::File, io::{self, BufRead}, collections::HashSet, cmp::{min, max}};
+mod expansion;
+mod universe;
 
+use std::{fs::File, io::{self, BufRead}, collections::HashSet};
+use expansion::{Axis, Expansion, Uniform, PerAxis, ByRun, PerIndex};
+use universe::Universe;
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(PartialEq, Eq, Clone)]
 enum Space {
     GALAXY, EMPTY
 }
 
-fn distance(g1: &(usize, usize), g2: &(usize, usize), double_rows: &HashSet<usize>, double_columns: &HashSet<usize>, expansion: usize) -> usize {
-    let mut count = 0;
-
-    let xmin = min(g1.0, g2.0);
-    let xmax = max(g1.0, g2.0);
-    for x in xmin..xmax {
-        count += if double_columns.contains(&x) { expansion } else { 1 };
+// Position after the expansion of every coordinate up to max (included)
+fn expanded_coordinates(max: usize, empty: &HashSet<usize>, axis: Axis, expansion: &dyn Expansion) -> Vec<u128> {
+    let run = |i: usize| {
+        let before = (0..i).rev().take_while(|j| empty.contains(j)).count();
+        let after = (i+1..).take_while(|j| empty.contains(j)).count();
+        before + 1 + after
+    };
+    let mut res = Vec::with_capacity(max + 1);
+    let mut pos = 0;
+    for i in 0..=max {
+        res.push(pos);
+        pos += if empty.contains(&i) { expansion.size(axis, i, run(i)) } else { 1 };
     }
+    res
+}
 
-    let ymin = min(g1.1, g2.1);
-    let ymax = max(g1.1, g2.1);
-    for y in ymin..ymax {
-        count += if double_rows.contains(&y) { expansion } else { 1 };
+// Sum of |a - b| over all the pairs: once sorted, each value is
+// larger than all the previous ones
+fn sum_of_differences(mut v: Vec<u128>) -> u128 {
+    v.sort_unstable();
+    let mut res = 0;
+    let mut prefix = 0;
+    for (k, a) in v.iter().enumerate() {
+        res += a * k as u128 - prefix;
+        prefix += a;
     }
-    count
+    res
+}
+
+fn total_distance(galaxies: &[(usize, usize)], double_rows: &HashSet<usize>, double_columns: &HashSet<usize>, expansion: &dyn Expansion) -> u128 {
+    // No galaxies: the sums below are empty
+    let x_max = galaxies.iter().map(|(x, _y)| *x).max().unwrap_or(0);
+    let y_max = galaxies.iter().map(|(_x, y)| *y).max().unwrap_or(0);
+    let xs = expanded_coordinates(x_max, double_columns, Axis::Column, expansion);
+    let ys = expanded_coordinates(y_max, double_rows, Axis::Row, expansion);
+    sum_of_differences(galaxies.iter().map(|(x, _y)| xs[*x]).collect()) +
+    sum_of_differences(galaxies.iter().map(|(_x, y)| ys[*y]).collect())
 }
 
 fn parse_input() -> (Vec<(usize, usize)>, HashSet<usize>, HashSet<usize>) {
@@ -31,7 +59,7 @@ fn parse_input() -> (Vec<(usize, usize)>, HashSet<usize>, HashSet<usize>) {
 
     let map = lines.enumerate().map(|(y, line)| {
         let line = line.unwrap();
-        let line = line.chars().into_iter().enumerate().map(|(x, c)| {
+        let line = line.chars().enumerate().map(|(x, c)| {
             match c {
                 '.' => Space::EMPTY,
                 '#' => {
@@ -49,7 +77,7 @@ fn parse_input() -> (Vec<(usize, usize)>, HashSet<usize>, HashSet<usize>) {
     }).collect::<Vec<_>>();
 
     let occupied_columns = galaxies.iter().map(|(x, _y)| *x).collect::<HashSet<_>>();
-    let x_max = map.first().unwrap().len();
+    let x_max = map.first().map_or(0, |line| line.len());
     let double_columns = (0..x_max).collect::<HashSet<_>>()
         .difference(&occupied_columns)
         .cloned()
@@ -62,20 +90,40 @@ fn main() {
     let (galaxies, double_rows, double_columns) = parse_input();
     
     // First part
-    let mut sum = 0;
-    for i in 0..galaxies.len() {
-        for j in i+1..galaxies.len() {
-            sum += distance(&galaxies[i], &galaxies[j], &double_rows, &double_columns, 2);
-        }
-    }
+    let sum = total_distance(&galaxies, &double_rows, &double_columns, &Uniform(2));
     println!("Sum of distances: {}", sum);
 
     // Second part
-    let mut sum = 0;
-    for i in 0..galaxies.len() {
-        for j in i+1..galaxies.len() {
-            sum += distance(&galaxies[i], &galaxies[j], &double_rows, &double_columns, 1_000_000);
-        }
-    }
+    let sum = total_distance(&galaxies, &double_rows, &double_columns, &Uniform(1_000_000));
     println!("Sum of distances: {}", sum);
+
+    // Other expansion rules
+    let rules: Vec<(&str, Box<dyn Expansion>)> = vec![
+        ("rows 2, columns 10", Box::new(PerAxis { rows: 2, columns: 10 })),
+        ("runs grow quadratically", Box::new(ByRun(|run| (run * run) as u64 + 1))),
+        ("even lines 10, odd lines 100", Box::new(PerIndex(|_axis, i| if i % 2 == 0 { 10 } else { 100 }))),
+    ];
+    for (name, rule) in rules.iter() {
+        let sum = total_distance(&galaxies, &double_rows, &double_columns, rule.as_ref());
+        println!("Sum of distances ({}): {}", name, sum);
+    }
+
+    // Queries on the expanded universe (e.g., cargo run -- 10 for an expansion of 10)
+    if galaxies.is_empty() {
+        return;
+    }
+    let expansion = std::env::args().nth(1).map_or(2, |e| e.parse::<u64>().unwrap());
+    let universe = Universe::new(&galaxies, &double_rows, &double_columns, &Uniform(expansion));
+    if let Some((j, d)) = universe.nearest(0) {
+        println!("Nearest to galaxy 0: galaxy {} at {}", j, d);
+    }
+    println!("Closest pairs: {:?}", universe.closest_pairs(3));
+    println!("Farthest pairs: {:?}", universe.farthest_pairs(3));
+    let eccentricities = (0..universe.len()).map(|i| universe.eccentricity(i)).collect::<Vec<_>>();
+    println!("Radius: {}, diameter: {}", eccentricities.iter().min().unwrap(), eccentricities.iter().max().unwrap());
+    let diameter = *eccentricities.iter().max().unwrap();
+    let width = (diameter / 10).max(1);
+    for (start, count) in universe.histogram(width) {
+        println!("Distances {}..{}: {}", start, start + width, count);
+    }
 }
@@ -0,0 +1,80 @@
+use std::collections::{BTreeMap, BinaryHeap, HashSet};
+
+use crate::expanded_coordinates;
+use crate::expansion::{Axis, Expansion};
+
+// Galaxies in the expanded universe
+pub struct Universe {
+    positions: Vec<(u128, u128)>,
+}
+
+impl Universe {
+    pub fn new(galaxies: &[(usize, usize)], double_rows: &HashSet<usize>, double_columns: &HashSet<usize>, expansion: &dyn Expansion) -> Self {
+        let x_max = galaxies.iter().map(|(x, _y)| *x).max().unwrap_or(0);
+        let y_max = galaxies.iter().map(|(_x, y)| *y).max().unwrap_or(0);
+        let xs = expanded_coordinates(x_max, double_columns, Axis::Column, expansion);
+        let ys = expanded_coordinates(y_max, double_rows, Axis::Row, expansion);
+        let positions = galaxies.iter().map(|(x, y)| (xs[*x], ys[*y])).collect();
+        Self { positions }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn distance(&self, i: usize, j: usize) -> u128 {
+        let (x1, y1) = self.positions[i];
+        let (x2, y2) = self.positions[j];
+        x1.abs_diff(x2) + y1.abs_diff(y2)
+    }
+
+    fn pairs(&self) -> impl Iterator<Item = (u128, usize, usize)> + '_ {
+        (0..self.len()).flat_map(move |i| (i+1..self.len()).map(move |j| (self.distance(i, j), i, j)))
+    }
+
+    // Closest other galaxy, with its distance
+    pub fn nearest(&self, i: usize) -> Option<(usize, u128)> {
+        (0..self.len())
+            .filter(|j| *j != i)
+            .map(|j| (j, self.distance(i, j)))
+            .min_by_key(|(_j, d)| *d)
+    }
+
+    // Distance to the farthest galaxy
+    pub fn eccentricity(&self, i: usize) -> u128 {
+        (0..self.len()).map(|j| self.distance(i, j)).max().unwrap_or(0)
+    }
+
+    // The k pairs with the smallest distance, as (distance, i, j) from the closest
+    pub fn closest_pairs(&self, k: usize) -> Vec<(u128, usize, usize)> {
+        let mut heap = BinaryHeap::new();
+        for pair in self.pairs() {
+            heap.push(pair);
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec()
+    }
+
+    // The k pairs with the largest distance, as (distance, i, j) from the farthest
+    pub fn farthest_pairs(&self, k: usize) -> Vec<(u128, usize, usize)> {
+        let mut heap = BinaryHeap::new();
+        for (d, i, j) in self.pairs() {
+            heap.push(std::cmp::Reverse((d, i, j)));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec().into_iter().map(|r| r.0).collect()
+    }
+
+    // Number of pairs for each range of distances [n * width, (n + 1) * width)
+    pub fn histogram(&self, width: u128) -> BTreeMap<u128, usize> {
+        let mut res = BTreeMap::new();
+        for (d, _i, _j) in self.pairs() {
+            *res.entry(d / width * width).or_default() += 1;
+        }
+        res
+    }
+}
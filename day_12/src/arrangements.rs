@@ -0,0 +1,138 @@
+// Concrete arrangements of a record, built on the counts of Record::ways
+
+use num::{BigInt, BigRational, BigUint, One, ToPrimitive, Zero};
+
+use crate::{Record, Spring};
+
+pub fn to_string(springs: &[Spring]) -> String {
+    springs.iter()
+        .map(|s| match s {
+            Spring::O => '.',
+            Spring::D => '#',
+            Spring::U => '?',
+        })
+        .collect()
+}
+
+// All the arrangements, generated one at a time (operational springs as early as possible first)
+pub struct Arrangements<'a> {
+    record: &'a Record,
+    ways: Vec<Vec<BigUint>>,
+    next: BigUint,
+}
+
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<Spring>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.ways[0][0] {
+            return None;
+        }
+        let res = self.record.unrank(&self.ways, self.next.clone());
+        self.next += 1_u32;
+        Some(res)
+    }
+}
+
+impl Record {
+    pub fn arrangements(&self) -> Arrangements<'_> {
+        Arrangements { record: self, ways: self.ways(), next: BigUint::zero() }
+    }
+
+    // The k-th arrangement: at each step, the arrangements with an operational spring come first
+    fn unrank(&self, ways: &[Vec<BigUint>], mut k: BigUint) -> Vec<Spring> {
+        let mut res = Vec::with_capacity(self.springs.len());
+        let (mut i, mut g) = (0, 0);
+        while i < self.springs.len() {
+            if self.springs[i] != Spring::D {
+                if k < ways[i + 1][g] {
+                    res.push(Spring::O);
+                    i += 1;
+                    continue;
+                }
+                k -= &ways[i + 1][g];
+            }
+            // Not enough arrangements with an operational spring: a group starts here
+            let len = self.groups[g];
+            res.extend(std::iter::repeat_n(Spring::D, len));
+            if i + len < self.springs.len() {
+                res.push(Spring::O);
+            }
+            i = self.after(i, len);
+            g += 1;
+        }
+        res
+    }
+
+    // Uniformly random arrangement (None if there is no valid arrangement)
+    pub fn sample(&self, random: &mut impl FnMut() -> u64) -> Option<Vec<Spring>> {
+        let ways = self.ways();
+        if ways[0][0].is_zero() {
+            return None;
+        }
+        // 64 more random bits than the total, so that the modulo is (almost) uniform
+        let words = ways[0][0].bits().div_ceil(64) + 1;
+        let random = (0..words).fold(BigUint::zero(), |res, _i| (res << 64) + random());
+        let k = random % &ways[0][0];
+        Some(self.unrank(&ways, k))
+    }
+
+    // For each position, the number of arrangements where the spring is damaged, and the total
+    pub fn damaged_counts(&self) -> (Vec<BigUint>, BigUint) {
+        let n = self.springs.len();
+        let ways = self.ways();
+
+        // before[i][g]: arrangements of springs[..i] with groups[..g], where the spring before i is operational
+        let mut before = vec![vec![BigUint::zero(); self.groups.len() + 1]; n + 1];
+        before[0][0] = BigUint::one();
+        // Arrangements with a group starting at i are counted in [i, i + len):
+        // added when the group starts, removed after it ends
+        let mut starts = vec![BigUint::zero(); n + 1];
+        let mut ends = vec![BigUint::zero(); n + 1];
+        for i in 0..n {
+            for g in 0..=self.groups.len() {
+                if before[i][g].is_zero() {
+                    continue;
+                }
+                let c = before[i][g].clone();
+                if self.springs[i] != Spring::D {
+                    before[i + 1][g] += &c;
+                }
+                if g < self.groups.len() && self.fits(i, self.groups[g]) {
+                    let len = self.groups[g];
+                    let next = self.after(i, len);
+                    let damaged = &c * &ways[next][g + 1];
+                    before[next][g + 1] += c;
+                    starts[i] += &damaged;
+                    ends[i + len] += damaged;
+                }
+            }
+        }
+
+        let mut current = BigUint::zero();
+        let damaged = (0..n)
+            .map(|i| {
+                // Groups ending at i started before, so they have already been added
+                current += &starts[i];
+                current -= &ends[i];
+                current.clone()
+            })
+            .collect();
+        (damaged, ways[0][0].clone())
+    }
+
+    // For each position, the fraction of the arrangements where the spring is damaged
+    // (None if there is no valid arrangement)
+    pub fn damaged_probabilities(&self) -> Option<Vec<f64>> {
+        let (damaged, total) = self.damaged_counts();
+        if total.is_zero() {
+            return None;
+        }
+        // Exact ratio first: both counts may be too large for a float
+        let total = BigInt::from(total);
+        let res = damaged.into_iter()
+            .map(|d| BigRational::new(BigInt::from(d), total.clone()).to_f64().unwrap())
+            .collect();
+        Some(res)
+    }
+}
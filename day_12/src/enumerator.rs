@@ -0,0 +1,77 @@
+// Brute-force enumeration of the arrangements: only usable on the folded records
+
+use crate::Spring;
+
+fn spring_ok(left: &[Spring], right: &[usize], missing_damaged: usize) -> bool {
+    if missing_damaged == 0 {
+        let mut left = left.to_owned();
+        left.iter_mut()
+            .filter(|s| **s == Spring::U)
+            .for_each(|s| *s = Spring::O);
+        let left = left.split(|el| *el == Spring::O);
+        let left = left.map(|x| x.len()).filter(|x| *x > 0).collect::<Vec<_>>();
+        left == right
+    } else {
+        let mut it = left.split(|el| *el == Spring::U);
+        let prefix = it.next().unwrap();
+        let prefix = prefix.split(|el| *el == Spring::O);
+        let prefix = prefix
+            .map(|x| x.len())
+            .filter(|x| *x > 0)
+            .collect::<Vec<_>>();
+
+        prefix.len() <= right.len() &&
+        (0..prefix.len().saturating_sub(1)).all(|i| {
+            prefix.get(i) == right.get(i)
+        }) &&
+        prefix.last() <= right.get(prefix.len().saturating_sub(1))
+    }
+}
+
+// There are still missing_damaged damaged springs
+// I try to put the next damaged (and call recursively)
+fn process_spring_rec(left: &[Spring], right: &[usize], missing_damaged: usize) -> usize {
+    let mut res = 0;
+    let num_unknown = left.iter().filter(|c| **c == Spring::U).count();
+    if num_unknown < missing_damaged {
+        return 0;
+    }
+
+    for i in 0..num_unknown-missing_damaged+1 {
+        let mut left = left.to_owned();
+        let missing_damaged = missing_damaged - 1;
+        
+        left.iter_mut()
+            .filter(|s| **s == Spring::U)
+            .take(i + 1)
+            .enumerate()
+            .for_each(|(c, s)| {
+                *s = if c == i { Spring::D } else { Spring::O };
+            });
+            
+        if spring_ok(&left, right, missing_damaged) {
+            if missing_damaged == 0 {
+                res += 1;
+            } else {
+                res += process_spring_rec(&left, right, missing_damaged);
+            }
+        }
+    }
+
+    res
+}
+
+pub fn process_spring(left: &[Spring], right: &[usize]) -> usize {
+    let current_damaged = left.iter().filter(|c| **c == Spring::D).count();
+    let expected_damaged = right.iter().sum::<usize>();
+    if current_damaged > expected_damaged {
+        return 0;
+    }
+    let missing_damaged = expected_damaged - current_damaged;
+
+    if missing_damaged == 0 {
+        spring_ok(left, right, 0) as usize
+    } else {
+        process_spring_rec(left, right, missing_damaged)
+    }
+}
@@ -1,17 +1,87 @@
-use rayon::prelude::*;
+mod arrangements;
+mod solver;
+#[cfg(feature = "enumerator")]
+mod enumerator;
+
 use std::{
     fs::File,
     io::{self, BufRead},
 };
+use num::{BigUint, One, Zero};
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
-enum Spring {
+pub enum Spring {
     O,
     D,
     U,
 }
 
-fn parse_input() -> Vec<(Vec<Spring>, Vec<usize>)> {
+struct Record {
+    springs: Vec<Spring>,
+    groups: Vec<usize>,
+}
+
+impl Record {
+    fn new(springs: Vec<Spring>, groups: Vec<usize>) -> Self {
+        Self { springs, groups }
+    }
+
+    // Springs repeated factor times (separated by an unknown spring), and groups repeated factor times
+    fn unfold(&self, factor: usize) -> Self {
+        let mut springs = self.springs.clone();
+        springs.push(Spring::U);
+        springs = springs.repeat(factor);
+        springs.pop();
+        Self::new(springs, self.groups.repeat(factor))
+    }
+
+    // A group of k damaged springs can start at i (and is not followed by a damaged spring)
+    fn fits(&self, i: usize, k: usize) -> bool {
+        i + k <= self.springs.len()
+            && self.springs[i..i + k].iter().all(|s| *s != Spring::O)
+            && self.springs.get(i + k) != Some(&Spring::D)
+    }
+
+    // Where the next group can start, after a group of k springs starting at i
+    fn after(&self, i: usize, k: usize) -> usize {
+        (i + k + 1).min(self.springs.len())
+    }
+
+    // Dynamic programming, from the end of the record.
+    // ways[i][g] is the number of arrangements of springs[i..] with groups[g..],
+    // when the spring before i (if any) is operational.
+    // The counts grow exponentially with the unfold factor, hence the big integers.
+    fn ways(&self) -> Vec<Vec<BigUint>> {
+        let n = self.springs.len();
+        let mut ways = vec![vec![BigUint::zero(); self.groups.len() + 1]; n + 1];
+        ways[n][self.groups.len()] = BigUint::one();
+
+        for i in (0..n).rev() {
+            for g in 0..=self.groups.len() {
+                let mut w = BigUint::zero();
+                // Operational
+                if self.springs[i] != Spring::D {
+                    w += &ways[i + 1][g];
+                }
+                // Damaged: the whole group starts here
+                if g < self.groups.len() && self.fits(i, self.groups[g]) {
+                    w += &ways[self.after(i, self.groups[g])][g + 1];
+                }
+                ways[i][g] = w;
+            }
+        }
+        ways
+    }
+
+    fn combinations(&self) -> BigUint {
+        if !self.is_feasible() {
+            return BigUint::zero();
+        }
+        self.ways().swap_remove(0).swap_remove(0)
+    }
+}
+
+fn parse_input() -> Vec<Record> {
     let f = File::open("input/input.txt").unwrap();
     let lines = io::BufReader::new(f).lines();
 
@@ -19,8 +89,8 @@ fn parse_input() -> Vec<(Vec<Spring>, Vec<usize>)> {
         .map(|line| {
             let line = line.unwrap();
             let mut line = line.split_whitespace();
-            let left = line.next().unwrap().to_string();
-            let left = left
+            let springs = line.next().unwrap().to_string();
+            let springs = springs
                 .chars()
                 .map(|c| match c {
                     '.' => Spring::O,
@@ -30,87 +100,68 @@ fn parse_input() -> Vec<(Vec<Spring>, Vec<usize>)> {
                 })
                 .collect::<Vec<_>>();
 
-            let right = line
+            let groups = line
                 .next()
                 .unwrap()
                 .split(',')
                 .map(|c| c.parse::<usize>().unwrap())
                 .collect::<Vec<_>>();
 
-            (left, right)
+            Record::new(springs, groups)
         })
         .collect::<Vec<_>>()
 }
 
-fn spring_ok(left: &[Spring], right: &[usize], missing_damaged: usize) -> bool {
-    if missing_damaged == 0 {
-        let mut left = left.to_owned();
-        left.iter_mut()
-            .filter(|s| **s == Spring::U)
-            .for_each(|s| *s = Spring::O);
-        let left = left.split(|el| *el == Spring::O);
-        let left = left.map(|x| x.len()).filter(|x| *x > 0).collect::<Vec<_>>();
-        left == right
-    } else {
-        let mut it = left.split(|el| *el == Spring::U);
-        let prefix = it.next().unwrap();
-        let prefix = prefix.split(|el| *el == Spring::O);
-        let prefix = prefix
-            .map(|x| x.len())
-            .filter(|x| *x > 0)
-            .collect::<Vec<_>>();
-
-        prefix.len() <= right.len() &&
-        (0..prefix.len()-1).all(|i| {
-            prefix.get(i) == right.get(i)
-        }) &&
-        prefix.get(prefix.len()-1) <= right.get(prefix.len()-1)
-    }
+// Row clues, an empty line, column clues (e.g., 1,3); 0 for an empty line
+fn parse_nonogram(path: &str) -> solver::Nonogram {
+    let f = File::open(path).unwrap();
+    let lines = io::BufReader::new(f).lines().map(|line| line.unwrap()).collect::<Vec<_>>();
+    let mut clues = lines.split(|line| line.trim().is_empty())
+        .map(|block| {
+            block.iter()
+                .map(|line| {
+                    line.split(',')
+                        .map(|c| c.trim().parse::<usize>().unwrap())
+                        .filter(|c| *c > 0)
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        });
+    let rows = clues.next().unwrap();
+    let columns = clues.next().expect("Missing column clues");
+    solver::Nonogram::new(rows, columns)
 }
 
-// There are still missing_damaged damaged springs
-// I try to put the next damaged (and call recursively)
-fn process_spring_rec(left: &[Spring], right: &[usize], missing_damaged: usize) -> usize {
-    let mut res = 0;
-    let num_unknown = left.iter().filter(|c| **c == Spring::U).count();
-
-    for i in 0..num_unknown-missing_damaged+1 {
-        let mut left = left.to_owned();
-        let missing_damaged = missing_damaged - 1;
-        
-        left.iter_mut()
-            .filter(|s| **s == Spring::U)
-            .take(i + 1)
-            .enumerate()
-            .for_each(|(c, s)| {
-                *s = if c == i { Spring::D } else { Spring::O };
-            });
-            
-        if spring_ok(&left, &right, missing_damaged) {
-            if missing_damaged == 0 {
-                res += 1;
-            } else {
-                res += process_spring_rec(&left, &right, missing_damaged);
-            }
-        }
+fn explain(record: &Record) {
+    println!("{} {:?}", arrangements::to_string(&record.springs), record.groups);
+    println!("Arrangements: {}", record.combinations());
+    match record.simplify() {
+        Some(simplified) => println!("Simplified: {}", arrangements::to_string(&simplified.springs)),
+        None => println!("No valid arrangement"),
+    }
+    for arrangement in record.arrangements().take(10) {
+        println!("  {}", arrangements::to_string(&arrangement));
     }
 
-    res
-}
-
-fn process_spring(left: &[Spring], right: &[usize]) -> usize {
-    let current_damaged = left.iter().filter(|c| **c == Spring::D).count();
-    let expected_damaged = right.iter().sum::<usize>();
-    let missing_damaged = expected_damaged - current_damaged;
-
-    let res = if missing_damaged == 0 {
-        1
-    } else {
-        process_spring_rec(left, right, missing_damaged)
+    let mut state = 0x2545F4914F6CDD1D_u64;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
     };
+    if let Some(arrangement) = record.sample(&mut random) {
+        println!("Random: {}", arrangements::to_string(&arrangement));
+    }
 
-    println!(">> {}", res);
-    res
+    // Probability that each unknown spring is damaged
+    if let Some(probabilities) = record.damaged_probabilities() {
+        for (i, p) in probabilities.iter().enumerate() {
+            if record.springs[i] == Spring::U {
+                println!("  {:>3}: {:.3}", i, p);
+            }
+        }
+    }
 }
 
 fn main() {
@@ -119,23 +170,38 @@ fn main() {
     // First part
     let sum = input
         .iter()
-        .map(|(left, right)| process_spring(left, right))
-        .sum::<usize>();
-
+        .map(|record| record.combinations())
+        .sum::<BigUint>();
     println!("Sum: {}", sum);
 
+    // Check against the brute-force enumerator (cargo run --features enumerator)
+    #[cfg(feature = "enumerator")]
+    for record in input.iter() {
+        let expected = enumerator::process_spring(&record.springs, &record.groups);
+        assert_eq!(record.combinations(), BigUint::from(expected), "Wrong count for {:?}", record.springs);
+    }
+
     // Second part
     let sum = input
-        .par_iter()
-        .map(|(left, right)| {
-            let mut left = left.clone();
-            left.push(Spring::U);
-            left = left.repeat(5);
-            left.pop();
-            (left, right.repeat(5))
-        })
-        .map(|(left, right)| process_spring(&left, &right))
-        .sum::<usize>();
-
+        .iter()
+        .map(|record| record.unfold(5))
+        .map(|record| record.combinations())
+        .sum::<BigUint>();
     println!("Sum: {}", sum);
+
+    // Details of a single record (e.g., cargo run -- explain 3)
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.len() >= 3 && args[1] == "explain" {
+        let record = &input[args[2].parse::<usize>().unwrap()];
+        let factor = args.get(3).map_or(1, |f| f.parse::<usize>().unwrap());
+        explain(&record.unfold(factor));
+    }
+
+    // Line solving on a 2D puzzle (e.g., cargo run -- nonogram input/nonogram_example.txt)
+    if args.len() == 3 && args[1] == "nonogram" {
+        match parse_nonogram(&args[2]).solve() {
+            Some(grid) => grid.iter().for_each(|row| println!("{}", arrangements::to_string(row))),
+            None => println!("No solution"),
+        }
+    }
 }
@@ -0,0 +1,137 @@
+// Line solving, as in nonograms: springs that are the same in every arrangement.
+// Only feasibility is tracked, without counting the arrangements, so lines of any length work.
+// Counting checks feasibility first, so records without arrangements skip the big integers.
+
+use crate::{Record, Spring};
+
+impl Record {
+    // feasible[i][g]: springs[i..] can hold groups[g..], with the spring before i operational.
+    fn feasible(&self) -> Vec<Vec<bool>> {
+        let n = self.springs.len();
+        let groups = self.groups.len();
+
+        let mut feasible = vec![vec![false; groups + 1]; n + 1];
+        feasible[n][groups] = true;
+        for i in (0..n).rev() {
+            for g in 0..=groups {
+                feasible[i][g] = (self.springs[i] != Spring::D && feasible[i + 1][g])
+                    || (g < groups && self.fits(i, self.groups[g]) && feasible[self.after(i, self.groups[g])][g + 1]);
+            }
+        }
+        feasible
+    }
+
+    // reachable[i][g]: springs[..i] can hold groups[..g], with the spring before i operational.
+    fn reachable(&self) -> Vec<Vec<bool>> {
+        let n = self.springs.len();
+        let groups = self.groups.len();
+
+        let mut reachable = vec![vec![false; groups + 1]; n + 1];
+        reachable[0][0] = true;
+        for i in 0..n {
+            for g in 0..=groups {
+                if !reachable[i][g] {
+                    continue;
+                }
+                if self.springs[i] != Spring::D {
+                    reachable[i + 1][g] = true;
+                }
+                if g < groups && self.fits(i, self.groups[g]) {
+                    reachable[self.after(i, self.groups[g])][g + 1] = true;
+                }
+            }
+        }
+
+        reachable
+    }
+
+    pub fn is_feasible(&self) -> bool {
+        self.feasible()[0][0]
+    }
+
+    // Same record, with the unknown springs that are forced in every arrangement replaced
+    // (None if there is no valid arrangement)
+    pub fn simplify(&self) -> Option<Record> {
+        let n = self.springs.len();
+        let feasible = self.feasible();
+        if !feasible[0][0] {
+            return None;
+        }
+        let reachable = self.reachable();
+
+        // Springs that are operational, and damaged, in at least one arrangement
+        let mut operational = vec![false; n];
+        let mut damaged = vec![0_isize; n + 1];
+        for i in 0..n {
+            for g in 0..=self.groups.len() {
+                if !reachable[i][g] {
+                    continue;
+                }
+                if self.springs[i] != Spring::D && feasible[i + 1][g] {
+                    operational[i] = true;
+                }
+                if g < self.groups.len() && self.fits(i, self.groups[g]) {
+                    let len = self.groups[g];
+                    if feasible[self.after(i, len)][g + 1] {
+                        // The group covers [i, i + len), followed by an operational spring
+                        damaged[i] += 1;
+                        damaged[i + len] -= 1;
+                        if i + len < n {
+                            operational[i + len] = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut covering = 0;
+        let springs = self.springs.iter()
+            .enumerate()
+            .map(|(i, s)| {
+                covering += damaged[i];
+                match (operational[i], covering > 0) {
+                    _ if *s != Spring::U => *s,
+                    (true, false) => Spring::O,
+                    (false, true) => Spring::D,
+                    _ => Spring::U,
+                }
+            })
+            .collect();
+        Some(Record::new(springs, self.groups.clone()))
+    }
+}
+
+// 2D puzzle: every row and every column is a record
+pub struct Nonogram {
+    rows: Vec<Vec<usize>>,
+    columns: Vec<Vec<usize>>,
+}
+
+impl Nonogram {
+    pub fn new(rows: Vec<Vec<usize>>, columns: Vec<Vec<usize>>) -> Self {
+        Self { rows, columns }
+    }
+
+    // Simplifies rows and columns until nothing changes.
+    // Cells that are still unknown cannot be deduced one line at a time.
+    // Returns None if the puzzle has no solution.
+    pub fn solve(&self) -> Option<Vec<Vec<Spring>>> {
+        let mut grid = vec![vec![Spring::U; self.columns.len()]; self.rows.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (r, groups) in self.rows.iter().enumerate() {
+                let record = Record::new(grid[r].clone(), groups.clone()).simplify()?;
+                changed |= record.springs != grid[r];
+                grid[r] = record.springs;
+            }
+            for (c, groups) in self.columns.iter().enumerate() {
+                let column = grid.iter().map(|row| row[c]).collect::<Vec<_>>();
+                let record = Record::new(column.clone(), groups.clone()).simplify()?;
+                changed |= record.springs != column;
+                grid.iter_mut().zip(record.springs).for_each(|(row, s)| row[c] = s);
+            }
+        }
+        Some(grid)
+    }
+}